@@ -0,0 +1,595 @@
+use crate::config::Config;
+use crate::copy;
+use crate::hooks::{HookEvent, HookRunner};
+use crate::worktree::open_current_repo;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use clap::Args as ClapArgs;
+use git2::{BranchType, Repository, Worktree, WorktreeAddOptions, WorktreeLockStatus};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Arguments for `wtm-select add`
+#[derive(ClapArgs, Debug)]
+pub struct AddArgs {
+    /// Always create a new branch (same as `git worktree add -b`)
+    #[arg(short = 'b', long = "new-branch")]
+    pub new_branch: bool,
+
+    /// Branch to check out in the new worktree
+    pub branch: String,
+
+    /// Commit to start the new branch from (only with -b, default: HEAD)
+    pub start_point: Option<String>,
+
+    /// Only report where the worktree would go and which files would be copied or linked
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Print `<path>\t<branch>` instead of just the path, for wrappers that need both
+    #[arg(long)]
+    pub porcelain: bool,
+}
+
+/// Result of `add_worktree`
+#[derive(Debug, PartialEq, Eq)]
+pub struct AddedWorktree {
+    pub path: PathBuf,
+    /// Previous location when an existing worktree was moved instead of created
+    pub moved_from: Option<PathBuf>,
+}
+
+/// Entry point for the `add` subcommand: creates the worktree and prints its path for the shell
+pub fn run(args: AddArgs) -> Result<()> {
+    let repo = open_current_repo()?;
+    if repo.is_bare() {
+        bail!("Cannot add a worktree from a bare repository");
+    }
+
+    let hooks = HookRunner::for_repo(&repo)?;
+    let worktree_path = worktree_path(
+        hooks.project_root(),
+        hooks.config(),
+        &args.branch,
+        Local::now(),
+    )?;
+
+    if args.dry_run {
+        eprintln!("Would add worktree: {}", worktree_path.display());
+        let planned = plan_files(&hooks, &worktree_path, &args.branch)?;
+        eprint!("{}", copy::format_plan(&planned));
+        return Ok(());
+    }
+
+    if let Some(parent) = worktree_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    hooks.run(HookEvent::PreAdd, &worktree_path, &args.branch)?;
+
+    let added = add_worktree(
+        &repo,
+        &args.branch,
+        args.start_point.as_deref(),
+        args.new_branch,
+        &worktree_path,
+    )?;
+
+    match &added.moved_from {
+        Some(from) => eprintln!(
+            "Moved worktree: {} -> {}",
+            from.display(),
+            added.path.display()
+        ),
+        None => eprintln!("Created worktree: {}", added.path.display()),
+    }
+
+    let event = match added.moved_from {
+        Some(_) => HookEvent::PostMove,
+        None => {
+            let planned = plan_files(&hooks, &added.path, &args.branch)?;
+            eprint!("{}", copy::format_plan(&planned));
+            copy::apply(&planned, hooks.project_root(), &added.path)?;
+            HookEvent::PostAdd
+        }
+    };
+    hooks.run(event, &added.path, &args.branch)?;
+
+    // Output path for shell to cd
+    if args.porcelain {
+        println!("{}\t{}", added.path.display(), args.branch);
+    } else {
+        println!("{}", added.path.display());
+    }
+    Ok(())
+}
+
+/// The `copy` and `symlink` rules from wt.config.yml, matched against the main worktree
+fn plan_files(
+    hooks: &HookRunner,
+    worktree_path: &Path,
+    branch: &str,
+) -> Result<Vec<copy::PlannedFile>> {
+    let config = hooks.config();
+    copy::plan(
+        &config.copy,
+        &config.symlink,
+        hooks.project_root(),
+        worktree_path,
+        branch,
+    )
+}
+
+/// Timestamp prefix of the directories created with the default `dir_template`
+const DIR_TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Where `wt add` puts the worktree for `branch`: `dir_template` inside `worktree_dir`,
+/// both from wt.config.yml with their placeholders filled in.
+///
+/// A relative `worktree_dir` is resolved from `project_root`; `~/` is the home directory.
+pub fn worktree_path(
+    project_root: &Path,
+    config: &Config,
+    branch: &str,
+    now: DateTime<Local>,
+) -> Result<PathBuf> {
+    let dir = render_dir_template(&config.worktree_dir, project_root, branch, now);
+    let dir = match dir.strip_prefix("~/") {
+        Some(rest) => home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Cannot expand ~ in worktree_dir: HOME is not set"))?
+            .join(rest),
+        None => project_root.join(dir),
+    };
+
+    let name = render_dir_template(&config.dir_template, project_root, branch, now);
+    let name = Path::new(&name);
+    if name.as_os_str().is_empty()
+        || name
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        bail!(
+            "dir_template '{}' must give a relative path without '..'",
+            config.dir_template
+        );
+    }
+    Ok(normalize(&dir.join(name)))
+}
+
+/// Fill in `{repo}`, `{branch}`, `{branch_slug}`, `{date}`, `{time}` and `{user}`
+fn render_dir_template(
+    template: &str,
+    project_root: &Path,
+    branch: &str,
+    now: DateTime<Local>,
+) -> String {
+    let repo = project_root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let user = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_default();
+
+    template
+        .replace("{repo}", &repo)
+        .replace("{branch_slug}", &branch_slug(branch))
+        .replace("{branch}", branch)
+        .replace("{date}", &now.format("%Y%m%d").to_string())
+        .replace("{time}", &now.format("%H%M%S").to_string())
+        .replace("{user}", &user)
+}
+
+/// `branch` with `/` replaced by `_`, as the original `wt add` named its directories
+fn branch_slug(branch: &str) -> String {
+    branch.replace('/', "_")
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// Resolve `.` and `..` without touching the file system, so `../repo.worktrees` prints nicely
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Creation time encoded by the default `dir_template`, as a unix timestamp
+pub fn dir_name_timestamp(dirname: &str) -> Option<i64> {
+    let prefix = dirname.get(.."YYYYmmdd_HHMMSS".len())?;
+    let naive = NaiveDateTime::parse_from_str(prefix, DIR_TIMESTAMP_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.timestamp())
+}
+
+/// Create (or move) a worktree for `branch` at `worktree_path`.
+///
+/// Mirrors the historical `wt add` behaviour:
+/// - a worktree that already has `branch` checked out is moved to `worktree_path`
+/// - `force_new` always creates a new branch from `start_point` (default: HEAD)
+/// - an existing local branch is checked out as is
+/// - a branch that only exists on `origin` gets a local tracking branch
+/// - otherwise a new branch is created from HEAD
+pub fn add_worktree(
+    repo: &Repository,
+    branch: &str,
+    start_point: Option<&str>,
+    force_new: bool,
+    worktree_path: &Path,
+) -> Result<AddedWorktree> {
+    if worktree_path.exists() {
+        bail!("Destination already exists: {}", worktree_path.display());
+    }
+
+    if let Some(existing) = find_linked_worktree_for_branch(repo, branch)? {
+        eprintln!(
+            "Moving existing worktree from '{}' to '{}'...",
+            existing.display(),
+            worktree_path.display()
+        );
+        move_worktree(&existing, worktree_path)?;
+        return Ok(AddedWorktree {
+            path: worktree_path.to_path_buf(),
+            moved_from: Some(existing),
+        });
+    }
+
+    let local_exists = repo.find_branch(branch, BranchType::Local).is_ok();
+    let remote_name = format!("origin/{branch}");
+    let remote_exists = repo.find_branch(&remote_name, BranchType::Remote).is_ok();
+
+    // Branches created here are deleted again when the worktree cannot be added
+    let created_branch = force_new || !local_exists;
+    let local_branch = if force_new {
+        let start = match start_point {
+            Some(rev) => repo
+                .revparse_single(rev)
+                .and_then(|obj| obj.peel_to_commit())
+                .with_context(|| format!("Invalid start point: {rev}"))?,
+            None => repo
+                .head()
+                .and_then(|h| h.peel_to_commit())
+                .context("Failed to resolve HEAD")?,
+        };
+        repo.branch(branch, &start, false)
+            .with_context(|| format!("Failed to create branch '{branch}'"))?
+    } else if local_exists {
+        eprintln!("Using existing local branch: {branch}");
+        repo.find_branch(branch, BranchType::Local)?
+    } else if remote_exists {
+        eprintln!("Creating local branch from remote: {branch}");
+        let remote_commit = repo
+            .find_branch(&remote_name, BranchType::Remote)?
+            .get()
+            .peel_to_commit()?;
+        let mut local = repo
+            .branch(branch, &remote_commit, false)
+            .with_context(|| format!("Failed to create branch '{branch}'"))?;
+        local
+            .set_upstream(Some(&remote_name))
+            .with_context(|| format!("Failed to set upstream to {remote_name}"))?;
+        local
+    } else {
+        eprintln!("Creating new branch: {branch}");
+        let head = repo
+            .head()
+            .and_then(|h| h.peel_to_commit())
+            .context("Failed to resolve HEAD")?;
+        repo.branch(branch, &head, false)
+            .with_context(|| format!("Failed to create branch '{branch}'"))?
+    };
+
+    let name = admin_name(repo, worktree_path)?;
+
+    let reference = local_branch.into_reference();
+    let mut opts = WorktreeAddOptions::new();
+    opts.reference(Some(&reference));
+
+    if let Err(e) = repo.worktree(&name, worktree_path, Some(&opts)) {
+        // Clean up a potentially created but broken worktree directory
+        if worktree_path.exists() {
+            let _ = fs::remove_dir_all(worktree_path);
+        }
+        if created_branch && let Ok(mut created) = repo.find_branch(branch, BranchType::Local) {
+            let _ = created.delete();
+        }
+        return Err(e).with_context(|| format!("Failed to create worktree for branch '{branch}'"));
+    }
+
+    Ok(AddedWorktree {
+        path: worktree_path.to_path_buf(),
+        moved_from: None,
+    })
+}
+
+/// Name of the admin dir under `.git/worktrees` for a new worktree: its directory name,
+/// with a number appended when taken (e.g. by `a/x` for `b/x`), as `git worktree add` does
+fn admin_name(repo: &Repository, worktree_path: &Path) -> Result<String> {
+    let name = worktree_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid worktree path: {}", worktree_path.display()))?;
+    let admin_root = repo.commondir().join("worktrees");
+    let mut candidate = name.to_string();
+    let mut counter = 1;
+    while admin_root.join(&candidate).exists() {
+        candidate = format!("{name}{counter}");
+        counter += 1;
+    }
+    Ok(candidate)
+}
+
+/// Find a linked (non-main) worktree that currently has `branch` checked out
+fn find_linked_worktree_for_branch(repo: &Repository, branch: &str) -> Result<Option<PathBuf>> {
+    let names = repo.worktrees().context("Failed to list worktrees")?;
+    for name in names.iter().flatten() {
+        let Ok(worktree) = repo.find_worktree(name) else {
+            continue;
+        };
+        let Ok(wt_repo) = Repository::open_from_worktree(&worktree) else {
+            continue;
+        };
+        if let Ok(head) = wt_repo.head()
+            && head.is_branch()
+            && head.shorthand() == Some(branch)
+        {
+            return Ok(Some(worktree.path().to_path_buf()));
+        }
+    }
+    Ok(None)
+}
+
+/// Move a linked worktree, equivalent to `git worktree move`.
+///
+/// libgit2 has no move API, so this renames the directory and rewrites the
+/// admin `gitdir` file to point at the new location.
+fn move_worktree(from: &Path, to: &Path) -> Result<()> {
+    let wt_repo = Repository::open(from).context("Failed to open worktree repository")?;
+    if !wt_repo.is_worktree() {
+        bail!("{} is not a linked worktree", from.display());
+    }
+    let worktree = Worktree::open_from_repository(&wt_repo).context("Failed to open worktree")?;
+    // For a linked worktree, path() is its admin dir under <commondir>/worktrees/<name>
+    let admin_dir = wt_repo.path().to_path_buf();
+    drop(wt_repo);
+
+    if let Ok(WorktreeLockStatus::Locked(reason)) = worktree.is_locked() {
+        bail!(
+            "Worktree {} is locked{}",
+            from.display(),
+            reason.map(|r| format!(": {r}")).unwrap_or_default()
+        );
+    }
+
+    let context = || {
+        format!(
+            "Failed to move worktree from {} to {}",
+            from.display(),
+            to.display()
+        )
+    };
+    match fs::rename(from, to) {
+        Ok(()) => {}
+        // A worktree_dir on another file system: copy, then remove the original
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy::copy_recursively(from, to) {
+                let _ = fs::remove_dir_all(to);
+                return Err(e).with_context(context);
+            }
+            fs::remove_dir_all(from).with_context(|| {
+                format!(
+                    "Copied worktree to {} but could not remove {}",
+                    to.display(),
+                    from.display()
+                )
+            })?;
+        }
+        Err(e) => return Err(e).with_context(context),
+    }
+    fs::write(
+        admin_dir.join("gitdir"),
+        format!("{}\n", to.join(".git").display()),
+    )
+    .context("Failed to update worktree gitdir")?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use git2::Signature;
+    use tempfile::TempDir;
+
+    /// Initialize a repository with a single commit on `main`
+    pub(crate) fn init_repo() -> (TempDir, Repository) {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        {
+            let sig = Signature::now("test", "test@example.com").unwrap();
+            let tree_id = repo.index().unwrap().write_tree().unwrap();
+            let tree = repo.find_tree(tree_id).unwrap();
+            let commit = repo
+                .commit(None, &sig, &sig, "initial", &tree, &[])
+                .unwrap();
+            let commit = repo.find_commit(commit).unwrap();
+            repo.branch("main", &commit, true).unwrap();
+            repo.set_head("refs/heads/main").unwrap();
+        }
+        (dir, repo)
+    }
+
+    fn head_branch(path: &Path) -> String {
+        let repo = Repository::open(path).unwrap();
+        repo.head().unwrap().shorthand().unwrap().to_string()
+    }
+
+    fn fixed_time() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()
+    }
+
+    #[test]
+    fn test_default_dir_template_replaces_slashes() {
+        let root = Path::new("/src/wtm");
+        let path =
+            worktree_path(root, &Config::default(), "feature/foo/bar", fixed_time()).unwrap();
+        assert_eq!(
+            path,
+            Path::new("/src/wtm/worktrees/20240102_030405_feature_foo_bar")
+        );
+    }
+
+    #[test]
+    fn test_worktree_dir_can_be_a_sibling_or_global() {
+        let root = Path::new("/src/wtm");
+        let config: Config = serde_yaml::from_str(
+            "worktree_dir: ../{repo}.worktrees\ndir_template: '{branch_slug}-{date}'\n",
+        )
+        .unwrap();
+        assert_eq!(
+            worktree_path(root, &config, "fix/x", fixed_time()).unwrap(),
+            Path::new("/src/wtm.worktrees/fix_x-20240102")
+        );
+
+        let config: Config =
+            serde_yaml::from_str("worktree_dir: /tmp/wt/{repo}\ndir_template: '{branch}'\n")
+                .unwrap();
+        assert_eq!(
+            worktree_path(root, &config, "fix/x", fixed_time()).unwrap(),
+            Path::new("/tmp/wt/wtm/fix/x")
+        );
+    }
+
+    #[test]
+    fn test_dir_template_must_stay_inside_worktree_dir() {
+        let config: Config = serde_yaml::from_str("dir_template: '../{branch}'\n").unwrap();
+        let err = worktree_path(Path::new("/src/wtm"), &config, "x", fixed_time()).unwrap_err();
+        assert!(err.to_string().contains("dir_template"));
+    }
+
+    #[test]
+    fn test_dir_name_timestamp_round_trips() {
+        let path = worktree_path(
+            Path::new("/r"),
+            &Config::default(),
+            "feature/foo",
+            Local::now(),
+        )
+        .unwrap();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let created = dir_name_timestamp(name).unwrap();
+        assert!((Local::now().timestamp() - created).abs() < 5);
+
+        assert_eq!(dir_name_timestamp("wtm"), None);
+        assert_eq!(dir_name_timestamp("feature_branch_name"), None);
+    }
+
+    #[test]
+    fn test_add_creates_new_branch() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().join("worktrees").join("wt-new");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        let added = add_worktree(&repo, "feature/new", None, false, &path).unwrap();
+
+        assert_eq!(added.path, path);
+        assert_eq!(added.moved_from, None);
+        assert!(path.join(".git").exists());
+        assert_eq!(head_branch(&path), "feature/new");
+    }
+
+    #[test]
+    fn test_add_tracks_remote_only_branch() {
+        let (dir, repo) = init_repo();
+        let head = repo.head().unwrap().target().unwrap();
+        repo.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        repo.reference("refs/remotes/origin/remote-only", head, false, "test")
+            .unwrap();
+        let path = dir.path().join("wt-remote");
+
+        add_worktree(&repo, "remote-only", None, false, &path).unwrap();
+
+        let local = repo.find_branch("remote-only", BranchType::Local).unwrap();
+        let upstream = local.upstream().unwrap();
+        assert_eq!(upstream.name().unwrap(), Some("origin/remote-only"));
+    }
+
+    #[test]
+    fn test_add_force_new_rejects_existing_branch() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().join("wt-main");
+
+        assert!(add_worktree(&repo, "main", None, true, &path).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_failed_add_deletes_the_created_branch() {
+        let (dir, repo) = init_repo();
+        // libgit2 cannot create a worktree below a regular file
+        fs::write(dir.path().join("file"), "").unwrap();
+        let path = dir.path().join("file").join("wt");
+
+        assert!(add_worktree(&repo, "fresh", None, false, &path).is_err());
+        assert!(repo.find_branch("fresh", BranchType::Local).is_err());
+        assert!(add_worktree(&repo, "main", None, false, &path).is_err());
+        assert!(repo.find_branch("main", BranchType::Local).is_ok());
+    }
+
+    #[test]
+    fn test_nested_dir_template_with_the_same_last_component() {
+        let (dir, repo) = init_repo();
+        let config: Config = serde_yaml::from_str("dir_template: '{branch}'\n").unwrap();
+        let first = worktree_path(dir.path(), &config, "a/x", fixed_time()).unwrap();
+        let second = worktree_path(dir.path(), &config, "b/x", fixed_time()).unwrap();
+        fs::create_dir_all(second.parent().unwrap()).unwrap();
+        fs::create_dir_all(first.parent().unwrap()).unwrap();
+
+        add_worktree(&repo, "a/x", None, false, &first).unwrap();
+        add_worktree(&repo, "b/x", None, false, &second).unwrap();
+
+        assert_eq!(head_branch(&first), "a/x");
+        assert_eq!(head_branch(&second), "b/x");
+        assert_eq!(repo.find_worktree("x").unwrap().path(), first.as_path());
+        assert_eq!(repo.find_worktree("x1").unwrap().path(), second.as_path());
+    }
+
+    #[test]
+    fn test_branch_slug_only_replaces_slashes() {
+        assert_eq!(branch_slug("feature/foo/bar"), "feature_foo_bar");
+        assert_eq!(branch_slug("foo+bar"), "foo+bar");
+        assert_eq!(branch_slug("feat@x"), "feat@x");
+    }
+
+    #[test]
+    fn test_add_moves_existing_worktree() {
+        let (dir, repo) = init_repo();
+        let first = dir.path().join("first");
+        let second = dir.path().join("second");
+        add_worktree(&repo, "topic", None, false, &first).unwrap();
+
+        let added = add_worktree(&repo, "topic", None, false, &second).unwrap();
+
+        assert_eq!(added.moved_from, Some(first.clone()));
+        assert!(!first.exists());
+        assert_eq!(head_branch(&second), "topic");
+        let worktree = repo.find_worktree("first").unwrap();
+        assert_eq!(worktree.path(), second.as_path());
+    }
+}
@@ -0,0 +1,771 @@
+use crate::worktree::{base_ahead_behind, find_base_commit, find_fork_point};
+use anyhow::{Context, Result};
+use git2::{Delta, Repository, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::UNIX_EPOCH;
+
+/// Cache file, relative to the common git dir
+const CACHE_FILE: &str = "wtm-cache.json";
+
+/// Bump when the stored layout changes; older files are ignored
+const CACHE_VERSION: u32 = 5;
+
+/// Changed files kept for the preview
+pub const MAX_CHANGED_FILES: usize = 30;
+
+/// Files changed vs the base branch kept for the preview
+pub const MAX_BASE_FILES: usize = 15;
+
+/// A file in `git status` or in a diff, with a one-letter status (A, M, D, R, C, ?)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub status: char,
+    pub path: String,
+}
+
+/// A file in `git status`, with the two-letter code of `git status --short`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusEntry {
+    /// `XY`: index status, then working tree status (`??` untracked, `UU` etc. conflicted)
+    pub code: String,
+    pub path: String,
+    /// Old path of a file renamed in the index
+    pub renamed_from: Option<String>,
+}
+
+impl StatusEntry {
+    /// Whether the file has unresolved merge conflicts
+    pub fn is_conflicted(&self) -> bool {
+        matches!(
+            self.code.as_str(),
+            "DD" | "AU" | "UD" | "UA" | "DU" | "AA" | "UU"
+        )
+    }
+}
+
+impl fmt::Display for StatusEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.renamed_from {
+            Some(from) => write!(f, "{} {from} -> {}", self.code, self.path),
+            None => write!(f, "{} {}", self.code, self.path),
+        }
+    }
+}
+
+/// Files in `git status` by kind; a file with staged and unstaged changes counts as both
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusCounts {
+    pub staged: usize,
+    pub unstaged: usize,
+    pub untracked: usize,
+    pub ignored: usize,
+    pub conflicted: usize,
+}
+
+/// Comparison of HEAD with the base branch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaseStats {
+    /// Base branch as configured, e.g. `origin/main`
+    pub branch: String,
+    /// Commit the base branch pointed at when this was computed
+    pub oid: String,
+    /// Merge base of HEAD and the base branch; the diff stats start here
+    pub fork_point: String,
+    pub ahead: usize,
+    pub behind: usize,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// First `MAX_BASE_FILES` changed files
+    pub files: Vec<FileChange>,
+}
+
+/// The expensive-to-compute facts about a worktree
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorktreeMetadata {
+    /// Unix timestamp of the HEAD commit
+    pub last_commit: Option<i64>,
+    pub summary: Option<String>,
+    /// Number of changed and untracked files
+    pub dirty: usize,
+    pub counts: StatusCounts,
+    /// First `MAX_CHANGED_FILES` changed and untracked files, conflicts first
+    pub changed_files: Vec<StatusEntry>,
+    /// `None` when the base branch cannot be found
+    pub base: Option<BaseStats>,
+}
+
+/// What a cached entry was computed from; any difference means it is stale
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheKey {
+    head: Option<String>,
+    /// Modification time of the worktree's index in nanoseconds
+    index_mtime: Option<u64>,
+    /// Modification time of the worktree's root directory in nanoseconds
+    workdir_mtime: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    key: CacheKey,
+    /// Everything but `base`, which is kept per base branch in `bases`
+    metadata: WorktreeMetadata,
+    /// Base stats by base branch: the picker columns use the base branch while the preview
+    /// may diff against an upstream or parent branch, and neither should evict the other
+    #[serde(default)]
+    bases: HashMap<String, BaseStats>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// On-disk cache of `WorktreeMetadata`, stored in the common git dir.
+///
+/// Entries are keyed by worktree path and reused while HEAD, the index mtime and the
+/// mtime of the worktree root are unchanged. That catches commits, staging and files
+/// added or removed at the top level, but not edits made without running git (the
+/// index is only rewritten by git commands), so cached status can lag behind the
+/// working tree; `get_fresh` rescans it. Base branch stats are kept per base branch
+/// and recomputed when that branch moves.
+#[derive(Debug)]
+pub struct MetadataCache {
+    file: PathBuf,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    /// Paths whose entries changed since loading; only these overwrite the file's entries
+    touched: Mutex<HashSet<String>>,
+    modified: AtomicBool,
+}
+
+impl MetadataCache {
+    /// Load the cache for the repository owning `repo`; a missing or unreadable file is empty
+    pub fn load(repo: &Repository) -> Self {
+        let file = repo.commondir().join(CACHE_FILE);
+        let entries = read_entries(&file);
+
+        Self {
+            file,
+            entries: Mutex::new(entries),
+            touched: Mutex::default(),
+            modified: AtomicBool::new(false),
+        }
+    }
+
+    /// Metadata for the worktree at `path`, from the cache when still valid.
+    ///
+    /// `None` when the worktree cannot be opened.
+    pub fn get(&self, path: &str, base_branch: &str) -> Option<WorktreeMetadata> {
+        self.lookup(path, base_branch, false)
+    }
+
+    /// Like `get`, but always rescans the status, which the cache key cannot vouch for
+    pub fn get_fresh(&self, path: &str, base_branch: &str) -> Option<WorktreeMetadata> {
+        self.lookup(path, base_branch, true)
+    }
+
+    fn lookup(
+        &self,
+        path: &str,
+        base_branch: &str,
+        fresh_status: bool,
+    ) -> Option<WorktreeMetadata> {
+        let repo = Repository::open(path).ok()?;
+        let key = CacheKey {
+            head: repo
+                .head()
+                .ok()
+                .and_then(|h| h.target())
+                .map(|oid| oid.to_string()),
+            index_mtime: index_mtime(&repo),
+            workdir_mtime: repo.workdir().and_then(mtime_nanos),
+        };
+        let base_oid = find_base_commit(&repo, base_branch).map(|c| c.id().to_string());
+
+        let cached = self.entries.lock().unwrap().get(path).cloned();
+        let (mut entry, mut changed) = match cached {
+            Some(entry) if entry.key == key => (entry, false),
+            // Base stats depend on HEAD as well, so a new key drops all of them
+            _ => (
+                CacheEntry {
+                    key,
+                    metadata: compute_local_metadata(&repo),
+                    bases: HashMap::new(),
+                },
+                true,
+            ),
+        };
+        if fresh_status && !changed {
+            let before = entry.metadata.clone();
+            set_status(&mut entry.metadata, scan_status(&repo));
+            changed = entry.metadata != before;
+        }
+        let base = match base_oid {
+            None => None,
+            Some(oid) => match entry.bases.get(base_branch).filter(|base| base.oid == oid) {
+                Some(base) => Some(base.clone()),
+                None => {
+                    let base = compute_base_stats(&repo, base_branch);
+                    if let Some(base) = &base {
+                        entry.bases.insert(base_branch.to_string(), base.clone());
+                        changed = true;
+                    }
+                    base
+                }
+            },
+        };
+
+        let metadata = WorktreeMetadata {
+            base,
+            ..entry.metadata.clone()
+        };
+        if changed {
+            self.entries.lock().unwrap().insert(path.to_string(), entry);
+            self.touched.lock().unwrap().insert(path.to_string());
+            self.modified.store(true, Ordering::Relaxed);
+        }
+        Some(metadata)
+    }
+
+    /// Write the cache back if anything changed, dropping worktrees not in `keep`.
+    ///
+    /// Entries written by other processes since loading are kept unless this cache
+    /// changed the same worktree, so concurrent pickers and previews don't lose each
+    /// other's work.
+    pub fn save(&self, keep: Option<&[String]>) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(keep) = keep {
+            let before = entries.len();
+            entries.retain(|path, _| keep.contains(path));
+            if entries.len() != before {
+                self.modified.store(true, Ordering::Relaxed);
+            }
+        }
+        if !self.modified.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let mut touched = self.touched.lock().unwrap();
+        let mut merged = read_entries(&self.file);
+        for path in touched.iter() {
+            if let Some(entry) = entries.get(path) {
+                merged.insert(path.clone(), entry.clone());
+            }
+        }
+        if let Some(keep) = keep {
+            merged.retain(|path, _| keep.contains(path));
+        }
+
+        let content = serde_json::to_string(&CacheFile {
+            version: CACHE_VERSION,
+            entries: merged.clone(),
+        })?;
+        write_atomically(&self.file, &content).context("Failed to write metadata cache")?;
+        *entries = merged;
+        touched.clear();
+        self.modified.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Entries of the cache file; a missing, unreadable or outdated file has none
+fn read_entries(file: &Path) -> HashMap<String, CacheEntry> {
+    fs::read_to_string(file)
+        .ok()
+        .and_then(|content| serde_json::from_str::<CacheFile>(&content).ok())
+        .filter(|cache| cache.version == CACHE_VERSION)
+        .map(|cache| cache.entries)
+        .unwrap_or_default()
+}
+
+/// Write through a temporary file so concurrent readers never see a partial cache
+pub(crate) fn write_atomically(file: &Path, content: &str) -> Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let tmp = file.with_extension(format!(
+        "tmp.{}.{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, file)?;
+    Ok(())
+}
+
+fn index_mtime(repo: &Repository) -> Option<u64> {
+    mtime_nanos(&repo.path().join("index"))
+}
+
+fn mtime_nanos(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    let nanos = modified.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    u64::try_from(nanos).ok()
+}
+
+/// Index (`X`) and working tree (`Y`) letters of `git status --short`, for files that
+/// are neither conflicted nor ignored
+fn status_code(status: Status) -> String {
+    if status.is_wt_new() && !status.is_index_new() {
+        return "??".to_string();
+    }
+    let index = if status.is_index_new() {
+        'A'
+    } else if status.is_index_modified() {
+        'M'
+    } else if status.is_index_deleted() {
+        'D'
+    } else if status.is_index_renamed() {
+        'R'
+    } else if status.is_index_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    let worktree = if status.is_wt_modified() {
+        'M'
+    } else if status.is_wt_deleted() {
+        'D'
+    } else if status.is_wt_renamed() {
+        'R'
+    } else if status.is_wt_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    format!("{index}{worktree}")
+}
+
+/// `git status --short` code of a conflicted file, from which sides of the merge have it
+fn conflict_code(ancestor: bool, ours: bool, theirs: bool) -> &'static str {
+    match (ancestor, ours, theirs) {
+        (true, false, false) => "DD",
+        (false, true, false) => "AU",
+        (true, false, true) => "UA",
+        (true, true, false) => "UD",
+        (false, false, true) => "DU",
+        (false, true, true) => "AA",
+        _ => "UU",
+    }
+}
+
+/// Codes of the conflicted paths in the index
+fn conflict_codes(repo: &Repository) -> HashMap<String, &'static str> {
+    let Ok(index) = repo.index() else {
+        return HashMap::new();
+    };
+    let Ok(conflicts) = index.conflicts() else {
+        return HashMap::new();
+    };
+    conflicts
+        .flatten()
+        .filter_map(|conflict| {
+            let entry = [&conflict.our, &conflict.their, &conflict.ancestor]
+                .into_iter()
+                .find_map(|side| side.as_ref())?;
+            let path = String::from_utf8_lossy(&entry.path).to_string();
+            let code = conflict_code(
+                conflict.ancestor.is_some(),
+                conflict.our.is_some(),
+                conflict.their.is_some(),
+            );
+            Some((path, code))
+        })
+        .collect()
+}
+
+/// Status entries and counts of a worktree
+fn scan_status(repo: &Repository) -> Option<(StatusCounts, Vec<StatusEntry>)> {
+    let mut status_opts = StatusOptions::new();
+    status_opts
+        .include_untracked(true)
+        .include_ignored(true)
+        .renames_head_to_index(true);
+    let statuses = repo.statuses(Some(&mut status_opts)).ok()?;
+    let conflicts = conflict_codes(repo);
+
+    let mut counts = StatusCounts::default();
+    let mut entries = Vec::new();
+    for entry in statuses.iter() {
+        let status = entry.status();
+        let path = entry.path().unwrap_or("?").to_string();
+        // Conflicts first: a conflicted file may carry other bits as well
+        let code = if status.is_conflicted() {
+            counts.conflicted += 1;
+            conflicts.get(&path).copied().unwrap_or("UU").to_string()
+        } else if status.is_ignored() {
+            counts.ignored += 1;
+            continue;
+        } else {
+            let code = status_code(status);
+            match code.as_bytes() {
+                b"??" => counts.untracked += 1,
+                [index, worktree] => {
+                    counts.staged += usize::from(*index != b' ');
+                    counts.unstaged += usize::from(*worktree != b' ');
+                }
+                _ => {}
+            }
+            code
+        };
+        // With rename detection the entry's path is the old one; show where it went
+        let rename = entry
+            .head_to_index()
+            .filter(|_| status.is_index_renamed())
+            .and_then(|delta| Some((delta.old_file().path()?, delta.new_file().path()?)))
+            .map(|(from, to)| {
+                (
+                    from.to_string_lossy().to_string(),
+                    to.to_string_lossy().to_string(),
+                )
+            });
+        let (renamed_from, path) = match rename {
+            Some((from, to)) => (Some(from), to),
+            None => (None, path),
+        };
+        entries.push((
+            status.is_conflicted(),
+            StatusEntry {
+                code,
+                path,
+                renamed_from,
+            },
+        ));
+    }
+    // Stable, so the rest keeps git's path order
+    entries.sort_by_key(|(conflicted, _)| !conflicted);
+    let entries = entries.into_iter().map(|(_, entry)| entry).collect();
+    Some((counts, entries))
+}
+
+/// Store the result of `scan_status`; a failed scan counts as clean
+fn set_status(metadata: &mut WorktreeMetadata, status: Option<(StatusCounts, Vec<StatusEntry>)>) {
+    let (counts, mut entries) = status.unwrap_or_default();
+    metadata.dirty = entries.len();
+    metadata.counts = counts;
+    entries.truncate(MAX_CHANGED_FILES);
+    metadata.changed_files = entries;
+}
+
+/// Scan the worktree: HEAD commit, status and diff vs the base branch
+pub fn compute_metadata(repo: &Repository, base_branch: &str) -> WorktreeMetadata {
+    WorktreeMetadata {
+        base: compute_base_stats(repo, base_branch),
+        ..compute_local_metadata(repo)
+    }
+}
+
+/// HEAD commit and status of the worktree, without the base branch stats
+fn compute_local_metadata(repo: &Repository) -> WorktreeMetadata {
+    let mut metadata = WorktreeMetadata::default();
+
+    if let Ok(commit) = repo.head().and_then(|h| h.peel_to_commit()) {
+        metadata.last_commit = Some(commit.time().seconds());
+        metadata.summary = commit.summary().map(str::to_string);
+    }
+
+    set_status(&mut metadata, scan_status(repo));
+    metadata
+}
+
+/// Ahead/behind of HEAD against `base_branch`, and diff stats of what HEAD changed since
+/// it forked from it (`git diff base...HEAD`)
+fn compute_base_stats(repo: &Repository, base_branch: &str) -> Option<BaseStats> {
+    let base_commit = find_base_commit(repo, base_branch)?;
+    let head_commit = repo.head().ok()?.peel_to_commit().ok()?;
+    let fork_point = find_fork_point(repo, base_branch)?;
+    let diff = repo
+        .diff_tree_to_tree(
+            Some(&fork_point.tree().ok()?),
+            Some(&head_commit.tree().ok()?),
+            None,
+        )
+        .ok()?;
+    let stats = diff.stats().ok()?;
+    let (ahead, behind) = base_ahead_behind(repo, base_branch)?;
+
+    let files = diff
+        .deltas()
+        .take(MAX_BASE_FILES)
+        .map(|delta| FileChange {
+            status: match delta.status() {
+                Delta::Added => 'A',
+                Delta::Deleted => 'D',
+                Delta::Modified => 'M',
+                Delta::Renamed => 'R',
+                Delta::Copied => 'C',
+                _ => '?',
+            },
+            path: delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    Some(BaseStats {
+        branch: base_branch.to_string(),
+        oid: base_commit.id().to_string(),
+        fork_point: fork_point.id().to_string(),
+        ahead,
+        behind,
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add::tests::init_repo;
+    use git2::Signature;
+
+    fn commit_file(repo: &Repository, name: &str, content: &str) {
+        let workdir = repo.workdir().unwrap();
+        fs::write(workdir.join(name), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("test", "test@example.com").unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, name, &tree, &[&parent])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_cache_round_trips_and_follows_head() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("notes.txt"), "wip").unwrap();
+
+        let cache = MetadataCache::load(&repo);
+        let first = cache.get(path, "origin/main").unwrap();
+        assert_eq!(first.dirty, 1);
+        assert_eq!(first.summary.as_deref(), Some("initial"));
+        cache.save(None).unwrap();
+
+        // A fresh load serves the stored entry while HEAD and the index are unchanged
+        let reloaded = MetadataCache::load(&repo);
+        assert_eq!(reloaded.get(path, "origin/main"), Some(first));
+        assert!(!reloaded.modified.load(Ordering::Relaxed));
+
+        commit_file(&repo, "notes.txt", "done");
+        let after = reloaded.get(path, "origin/main").unwrap();
+        assert_eq!(after.summary.as_deref(), Some("notes.txt"));
+        assert_eq!(after.dirty, 0);
+    }
+
+    #[test]
+    fn test_get_fresh_sees_edits_made_without_git() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().to_str().unwrap();
+        commit_file(&repo, "tracked.txt", "one");
+
+        let cache = MetadataCache::load(&repo);
+        assert_eq!(cache.get(path, "origin/main").unwrap().dirty, 0);
+
+        // Neither the index nor the worktree root changes when a file is edited in place
+        fs::write(dir.path().join("tracked.txt"), "two").unwrap();
+        assert_eq!(cache.get(path, "origin/main").unwrap().dirty, 0);
+        let fresh = cache.get_fresh(path, "origin/main").unwrap();
+        assert_eq!(fresh.counts.unstaged, 1);
+        assert_eq!(cache.get(path, "origin/main").unwrap().dirty, 1);
+
+        // Files created at the top level do change the key
+        fs::write(dir.path().join("new.txt"), "new").unwrap();
+        assert_eq!(cache.get(path, "origin/main").unwrap().counts.untracked, 1);
+    }
+
+    #[test]
+    fn test_base_stats_follow_the_base_branch() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().to_str().unwrap();
+        let base = repo.head().unwrap().target().unwrap();
+        repo.reference("refs/remotes/origin/main", base, true, "test")
+            .unwrap();
+        commit_file(&repo, "feature.txt", "one\ntwo\n");
+
+        let cache = MetadataCache::load(&repo);
+        let stats = cache.get(path, "origin/main").unwrap().base.unwrap();
+        assert_eq!((stats.ahead, stats.behind), (1, 0));
+        assert_eq!(stats.insertions, 2);
+        assert_eq!(
+            stats.files,
+            vec![FileChange {
+                status: 'A',
+                path: "feature.txt".to_string()
+            }]
+        );
+
+        // Moving the base branch refreshes only the base stats
+        let head = repo.head().unwrap().target().unwrap();
+        repo.reference("refs/remotes/origin/main", head, true, "test")
+            .unwrap();
+        let stats = cache.get(path, "origin/main").unwrap().base.unwrap();
+        assert_eq!((stats.ahead, stats.files_changed), (0, 0));
+    }
+
+    #[test]
+    fn test_base_stats_ignore_changes_made_on_the_base_branch() {
+        let (_dir, repo) = init_repo();
+        let fork = repo.head().unwrap().peel_to_commit().unwrap();
+
+        // origin/main moves on after the branch was cut
+        commit_file(&repo, "upstream.txt", "main\n");
+        let main_tip = repo.head().unwrap().target().unwrap();
+        repo.reference("refs/remotes/origin/main", main_tip, true, "test")
+            .unwrap();
+        repo.branch("topic", &fork, true).unwrap();
+        repo.set_head("refs/heads/topic").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        commit_file(&repo, "feature.txt", "topic\n");
+
+        let stats = compute_base_stats(&repo, "origin/main").unwrap();
+        assert_eq!((stats.ahead, stats.behind), (1, 1));
+        assert_eq!(stats.fork_point, fork.id().to_string());
+        assert_eq!(stats.files_changed, 1);
+        assert_eq!(stats.files[0].path, "feature.txt");
+    }
+
+    #[test]
+    fn test_base_stats_are_kept_per_base_branch() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().to_str().unwrap();
+        let base = repo.head().unwrap().target().unwrap();
+        repo.reference("refs/remotes/origin/main", base, true, "test")
+            .unwrap();
+        commit_file(&repo, "parent.txt", "parent");
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("parent", &parent, false).unwrap();
+        commit_file(&repo, "child.txt", "child");
+
+        let cache = MetadataCache::load(&repo);
+        let base = cache.get(path, "origin/main").unwrap().base.unwrap();
+        let parent = cache.get(path, "parent").unwrap().base.unwrap();
+        assert_eq!((base.ahead, parent.ahead), (2, 1));
+        cache.save(None).unwrap();
+
+        // Switching between the two is served from the cache without writing it again
+        assert_eq!(cache.get(path, "origin/main").unwrap().base, Some(base));
+        assert_eq!(cache.get(path, "parent").unwrap().base, Some(parent));
+        assert!(!cache.modified.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_concurrent_saves_keep_each_others_entries() {
+        let (dir, repo) = init_repo();
+        let main = dir.path().to_str().unwrap().to_string();
+        let linked = dir.path().join("wt");
+        crate::add::add_worktree(&repo, "topic", None, false, &linked).unwrap();
+        let linked = linked.to_str().unwrap().to_string();
+
+        let first = MetadataCache::load(&repo);
+        let second = MetadataCache::load(&repo);
+        first.get(&main, "origin/main").unwrap();
+        second.get(&linked, "origin/main").unwrap();
+        first.save(None).unwrap();
+        second.save(None).unwrap();
+
+        let entries = MetadataCache::load(&repo).entries.into_inner().unwrap();
+        assert!(entries.contains_key(&main));
+        assert!(entries.contains_key(&linked));
+    }
+
+    #[test]
+    fn test_save_drops_unknown_worktrees() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().to_str().unwrap().to_string();
+
+        let cache = MetadataCache::load(&repo);
+        cache.get(&path, "origin/main").unwrap();
+        cache.save(Some(&[])).unwrap();
+        assert!(
+            MetadataCache::load(&repo)
+                .entries
+                .lock()
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_status_separates_staged_unstaged_untracked_and_ignored() {
+        let (dir, repo) = init_repo();
+        let workdir = dir.path();
+        commit_file(&repo, ".gitignore", "*.log\n");
+        commit_file(&repo, "old.txt", "rename me\n");
+        commit_file(&repo, "both.txt", "one\n");
+
+        let mut index = repo.index().unwrap();
+        // Renamed and staged
+        fs::rename(workdir.join("old.txt"), workdir.join("new.txt")).unwrap();
+        index.remove_path(Path::new("old.txt")).unwrap();
+        index.add_path(Path::new("new.txt")).unwrap();
+        // Staged, then changed again
+        fs::write(workdir.join("both.txt"), "two\n").unwrap();
+        index.add_path(Path::new("both.txt")).unwrap();
+        index.write().unwrap();
+        fs::write(workdir.join("both.txt"), "three\n").unwrap();
+        fs::write(workdir.join("notes.md"), "untracked").unwrap();
+        fs::write(workdir.join("debug.log"), "ignored").unwrap();
+
+        let metadata = compute_metadata(&repo, "origin/main");
+        let lines: Vec<String> = metadata
+            .changed_files
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            ["MM both.txt", "R  old.txt -> new.txt", "?? notes.md"]
+        );
+        assert_eq!(
+            metadata.counts,
+            StatusCounts {
+                staged: 2,
+                unstaged: 1,
+                untracked: 1,
+                ignored: 1,
+                conflicted: 0,
+            }
+        );
+        assert_eq!(metadata.dirty, 3);
+    }
+
+    #[test]
+    fn test_conflicts_come_first_with_their_merge_sides() {
+        let (dir, repo) = init_repo();
+        commit_file(&repo, "a.txt", "a\n");
+        commit_file(&repo, "z.txt", "base\n");
+        fs::write(dir.path().join("a.txt"), "changed\n").unwrap();
+
+        let mut index = repo.index().unwrap();
+        // Both sides added z.txt: stage 2 (ours) and stage 3 (theirs), no ancestor
+        for stage in [2, 3] {
+            let mut entry = index.get_path(Path::new("z.txt"), 0).unwrap();
+            entry.flags |= stage << 12;
+            index.add(&entry).unwrap();
+        }
+        index.remove(Path::new("z.txt"), 0).unwrap();
+        index.write().unwrap();
+
+        let metadata = compute_metadata(&repo, "origin/main");
+        assert_eq!(metadata.changed_files[0].to_string(), "AA z.txt");
+        assert_eq!(metadata.changed_files[1].to_string(), " M a.txt");
+        assert_eq!(metadata.counts.conflicted, 1);
+        assert_eq!(metadata.counts.unstaged, 1);
+        assert_eq!(conflict_code(true, true, true), "UU");
+        assert_eq!(conflict_code(true, true, false), "UD");
+    }
+}
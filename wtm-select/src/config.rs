@@ -0,0 +1,752 @@
+use crate::copy::{FileRule, glob_matches};
+use crate::hooks::{DEFAULT_HOOK_TIMEOUT, HooksConfig};
+use crate::theme::{Theme, warning_prefix};
+use crate::worktree::{detect_base_branch, find_parent_branch, open_current_repo};
+use anyhow::{Result, bail};
+use clap::{Args as ClapArgs, Subcommand, ValueEnum};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Mutex;
+
+/// Configuration loaded from wt.config.yml and the other config layers
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Base branch for diffs, merge checks and ahead/behind
+    /// (default: detected from the remote's HEAD)
+    #[serde(default)]
+    pub base_branch: Option<String>,
+    /// Base branches for branches matching a glob, e.g. `release/*`; the first match wins
+    #[serde(default)]
+    pub base_overrides: Vec<BaseOverride>,
+    /// What the preview's diff compares the branch with
+    #[serde(default)]
+    pub diff_against: DiffAgainst,
+    /// Let `prune` pick worktrees whose last commit is older than this (e.g. `30d`, `2w`)
+    #[serde(default)]
+    pub prune_older_than: Option<String>,
+    /// Extra picker columns, shown between the state badge and the directory
+    #[serde(default)]
+    pub columns: Vec<Column>,
+    /// Default order of the picker rows
+    #[serde(default)]
+    pub sort: SortOrder,
+    /// Preview position and size in skim's `--preview-window` format
+    #[serde(default = "default_preview_window")]
+    pub preview_window: String,
+    /// Picker height in lines or percent of the terminal
+    #[serde(default = "default_height")]
+    pub height: String,
+    /// Terminals narrower than this many columns get the preview below the list instead
+    /// of beside it; 0 keeps `preview_window` as is
+    #[serde(default = "default_preview_narrow_width")]
+    pub preview_narrow_width: u16,
+    /// Commands run at points of a worktree's lifecycle
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Seconds a hook may run before it is killed
+    #[serde(default = "default_hook_timeout")]
+    pub hook_timeout: u64,
+    /// Directory new worktrees are created in, relative to the repository root
+    /// (placeholders as in `dir_template`)
+    #[serde(default = "default_worktree_dir")]
+    pub worktree_dir: String,
+    /// Name of a new worktree's directory; `{repo}`, `{branch}`, `{branch_slug}`,
+    /// `{date}`, `{time}` and `{user}` are filled in
+    #[serde(default = "default_dir_template")]
+    pub dir_template: String,
+    /// Files copied from the main worktree into new worktrees
+    #[serde(default)]
+    pub copy: Vec<FileRule>,
+    /// Files symlinked from the main worktree into new worktrees
+    #[serde(default)]
+    pub symlink: Vec<FileRule>,
+    /// Colors of the picker and its preview, and whether to use emoji
+    #[serde(default)]
+    pub theme: Theme,
+}
+
+/// `base` for branches matching `branch`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BaseOverride {
+    /// Glob over the branch name (`*` within a `/` segment, `**` across segments)
+    pub branch: String,
+    pub base: String,
+}
+
+/// Branch whose fork point the preview diffs against (`git diff <branch>...HEAD`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAgainst {
+    /// The base branch
+    #[default]
+    Base,
+    /// The branch's upstream, else the base branch
+    Upstream,
+    /// The local branch this one was stacked on, else the base branch
+    Parent,
+}
+
+/// Optional column in the picker row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    /// Ahead/behind the branch's upstream
+    Upstream,
+    /// Ahead/behind `base_branch`
+    Base,
+    /// Marker for uncommitted or untracked files
+    Dirty,
+}
+
+/// Order of the picker rows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Most often and most recently picked first
+    Frecency,
+    /// Most recent HEAD commit first
+    #[default]
+    Commit,
+    /// Branch name, alphabetically
+    Name,
+    /// Directory name, alphabetically
+    Dirname,
+    /// Most recently created worktree first
+    Created,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        // Keep in sync with the serde defaults so a missing file behaves like an empty one
+        Self {
+            base_branch: None,
+            base_overrides: Vec::new(),
+            diff_against: DiffAgainst::default(),
+            prune_older_than: None,
+            columns: Vec::new(),
+            sort: SortOrder::default(),
+            preview_window: default_preview_window(),
+            height: default_height(),
+            preview_narrow_width: default_preview_narrow_width(),
+            hooks: HooksConfig::default(),
+            hook_timeout: default_hook_timeout(),
+            worktree_dir: default_worktree_dir(),
+            dir_template: default_dir_template(),
+            copy: Vec::new(),
+            symlink: Vec::new(),
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Base branch configured for `branch`: the first matching `base_overrides` entry,
+    /// else `base_branch`
+    pub fn configured_base_branch(&self, branch: &str) -> Option<&str> {
+        self.base_overrides
+            .iter()
+            .find(|o| glob_matches(&o.branch, branch))
+            .map(|o| o.base.as_str())
+            .or(self.base_branch.as_deref())
+    }
+
+    /// Base branch for a worktree with `branch` checked out, detected from `repo` when
+    /// not configured
+    pub fn base_branch_for(&self, repo: &Repository, branch: &str) -> String {
+        self.configured_base_branch(branch)
+            .map_or_else(|| detect_base_branch(repo), str::to_string)
+    }
+
+    /// Branch the preview diff of `branch` starts from, as chosen by `diff_against`
+    pub fn diff_target(&self, repo: &Repository, branch: &str) -> String {
+        let base_branch = self.base_branch_for(repo, branch);
+        let target = match self.diff_against {
+            DiffAgainst::Base => None,
+            DiffAgainst::Upstream => repo
+                .branch_upstream_name(&format!("refs/heads/{branch}"))
+                .ok()
+                .and_then(|name| {
+                    let name = name.as_str()?;
+                    let short = name
+                        .strip_prefix("refs/remotes/")
+                        .or_else(|| name.strip_prefix("refs/heads/"))
+                        .unwrap_or(name);
+                    Some(short.to_string())
+                }),
+            DiffAgainst::Parent => find_parent_branch(repo, branch, &base_branch),
+        };
+        target.unwrap_or(base_branch)
+    }
+}
+
+fn default_hook_timeout() -> u64 {
+    DEFAULT_HOOK_TIMEOUT
+}
+
+fn default_preview_window() -> String {
+    "right:60%:wrap".to_string()
+}
+
+fn default_height() -> String {
+    "80%".to_string()
+}
+
+fn default_preview_narrow_width() -> u16 {
+    120
+}
+
+fn default_worktree_dir() -> String {
+    "worktrees".to_string()
+}
+
+fn default_dir_template() -> String {
+    "{date}_{time}_{branch_slug}".to_string()
+}
+
+/// Committed, per-repository config file in the root of a worktree
+pub const REPO_CONFIG_FILE: &str = "wt.config.yml";
+
+/// Untracked override in the git dir: the common one applies to every worktree,
+/// a linked worktree's own git dir only to that worktree
+pub const LOCAL_CONFIG_FILE: &str = "wtm.yml";
+
+/// Where a config layer comes from, lowest precedence first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Default,
+    /// `$XDG_CONFIG_HOME/wtm/config.yml`
+    Global,
+    /// `wt.config.yml` in the worktree
+    Repo,
+    /// `wtm.yml` in the common git dir
+    Local,
+    /// `wtm.yml` in a linked worktree's git dir
+    Worktree,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Default => "default",
+            Self::Global => "global",
+            Self::Repo => "repo",
+            Self::Local => "local",
+            Self::Worktree => "worktree",
+        })
+    }
+}
+
+/// A config file and the layer it belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    pub layer: Layer,
+    pub path: PathBuf,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.layer, self.path.display())
+    }
+}
+
+/// The effective config and, for every key set in a file, the files that set it
+#[derive(Debug)]
+pub struct LayeredConfig {
+    pub config: Config,
+    /// Top-level key -> files that set it, lowest precedence first
+    pub origins: BTreeMap<String, Vec<Origin>>,
+    /// Files left out because they could not be read or are invalid
+    pub errors: Vec<ConfigError>,
+}
+
+/// Load the configuration for the worktree at `repo_path`.
+///
+/// Layers are merged key by key, later ones winning: the global file, `wt.config.yml`
+/// in the worktree, `wtm.yml` in the common git dir and `wtm.yml` in the worktree's
+/// own git dir. Nested maps such as `hooks` are merged the same way; lists are replaced.
+/// Invalid files are left out with a warning on stderr.
+pub fn load_config(repo_path: &Path) -> Config {
+    let layered = load_layered_config(repo_path);
+    warn_config_errors(&layered.errors);
+    layered.config
+}
+
+/// Same as `load_config` without printing anything, also recording which file each key
+/// came from and which files were left out
+pub fn load_layered_config(repo_path: &Path) -> LayeredConfig {
+    load_layers(&config_layers(repo_path))
+}
+
+/// Candidate config files for the worktree at `repo_path`, lowest precedence first
+pub fn config_layers(repo_path: &Path) -> Vec<Origin> {
+    let mut layers = Vec::new();
+    if let Some(path) = global_config_path() {
+        layers.push(Origin {
+            layer: Layer::Global,
+            path,
+        });
+    }
+    layers.push(Origin {
+        layer: Layer::Repo,
+        path: repo_path.join(REPO_CONFIG_FILE),
+    });
+    if let Ok(repo) = Repository::open(repo_path) {
+        layers.push(Origin {
+            layer: Layer::Local,
+            path: repo.commondir().join(LOCAL_CONFIG_FILE),
+        });
+        if repo.is_worktree() {
+            layers.push(Origin {
+                layer: Layer::Worktree,
+                path: repo.path().join(LOCAL_CONFIG_FILE),
+            });
+        }
+    }
+    layers
+}
+
+/// `$XDG_CONFIG_HOME/wtm/config.yml`, or `~/.config/wtm/config.yml`
+fn global_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("wtm").join("config.yml"))
+}
+
+/// Merge the existing files among `layers`; a file that cannot be read or is not a
+/// valid config is left out and reported in `errors`
+fn load_layers(layers: &[Origin]) -> LayeredConfig {
+    let mut merged = Mapping::new();
+    let mut origins: BTreeMap<String, Vec<Origin>> = BTreeMap::new();
+    let mut errors = Vec::new();
+
+    for origin in layers {
+        let layer = match read_layer(&origin.path) {
+            Ok(Some(layer)) => layer,
+            Ok(None) => continue,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        for key in layer.keys().filter_map(Value::as_str) {
+            origins
+                .entry(key.to_string())
+                .or_default()
+                .push(origin.clone());
+        }
+        merge(&mut merged, layer);
+    }
+
+    // Every layer is valid on its own, so the merged map only fails in odd cases such as
+    // a hook split across files; fall back to the defaults and say so
+    let config = match serde_yaml::from_value(Value::Mapping(merged)) {
+        Ok(config) => config,
+        Err(e) => {
+            errors.push(ConfigError {
+                path: PathBuf::from("(merged config)"),
+                location: None,
+                message: e.to_string(),
+            });
+            Config::default()
+        }
+    };
+    LayeredConfig {
+        config,
+        origins,
+        errors,
+    }
+}
+
+/// Parse and validate one config file; `None` when it does not exist or is empty
+fn read_layer(path: &Path) -> Result<Option<Mapping>, ConfigError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(ConfigError {
+                path: path.to_path_buf(),
+                location: None,
+                message: e.to_string(),
+            });
+        }
+    };
+
+    let error = |e: serde_yaml::Error| ConfigError::from_yaml(path, e);
+    match serde_yaml::from_str::<Value>(&content).map_err(error)? {
+        Value::Null => Ok(None),
+        Value::Mapping(layer) => {
+            // Typed parse for unknown keys and wrong types, with their position in the file
+            serde_yaml::from_str::<Config>(&content).map_err(error)?;
+            Ok(Some(layer))
+        }
+        _ => Err(ConfigError {
+            path: path.to_path_buf(),
+            location: Some((1, 1)),
+            message: "expected a map of settings".to_string(),
+        }),
+    }
+}
+
+/// A config file that could not be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    /// Line and column, 1-based
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl ConfigError {
+    fn from_yaml(path: &Path, error: serde_yaml::Error) -> Self {
+        let location = error.location().map(|l| (l.line(), l.column()));
+        let mut message = error.to_string();
+        // The location is already part of the message; keep it in one place
+        if let Some((line, column)) = location
+            && let Some(stripped) =
+                message.strip_suffix(&format!(" at line {line} column {column}"))
+        {
+            message = stripped.to_string();
+        }
+        Self {
+            path: path.to_path_buf(),
+            location,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some((line, column)) => {
+                write!(
+                    f,
+                    "{}:{line}:{column}: {}",
+                    self.path.display(),
+                    self.message
+                )
+            }
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+/// Print each config error to stderr, once per file and process
+pub fn warn_config_errors(errors: &[ConfigError]) {
+    static WARNED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    let mut warned = WARNED.lock().unwrap();
+    for error in errors {
+        if !warned.contains(&error.path) {
+            eprintln!("{}Ignoring invalid config {error}", warning_prefix());
+            warned.push(error.path.clone());
+        }
+    }
+}
+
+/// Overlay `layer` on `base`: maps are merged recursively, anything else is replaced
+fn merge(base: &mut Mapping, layer: Mapping) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Mapping(existing)), Value::Mapping(nested)) => merge(existing, nested),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Arguments for `wtm-select config`
+#[derive(ClapArgs, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the effective configuration of the current worktree
+    Show {
+        /// Annotate every key with the file it came from
+        #[arg(long)]
+        origin: bool,
+    },
+    /// Check config files for unknown keys and invalid values (exits 1 on errors)
+    Validate {
+        /// Files to check (default: every config layer of the current worktree)
+        files: Vec<PathBuf>,
+    },
+}
+
+/// Entry point for the `config` subcommand
+pub fn run(args: ConfigArgs) -> Result<ExitCode> {
+    let layers = match &args.command {
+        ConfigCommand::Validate { files } if !files.is_empty() => files
+            .iter()
+            .map(|path| Origin {
+                layer: Layer::Repo,
+                path: path.clone(),
+            })
+            .collect(),
+        _ => {
+            let repo = open_current_repo()?;
+            let workdir = repo
+                .workdir()
+                .ok_or_else(|| anyhow::anyhow!("Cannot read the config of a bare repository"))?;
+            config_layers(workdir)
+        }
+    };
+
+    match args.command {
+        ConfigCommand::Show { origin } => {
+            let layered = load_layers(&layers);
+            warn_config_errors(&layered.errors);
+            print!("{}", format_config(&layered, origin)?);
+            Ok(ExitCode::SUCCESS)
+        }
+        ConfigCommand::Validate { .. } => {
+            let mut failed = false;
+            for origin in &layers {
+                match read_layer(&origin.path) {
+                    Ok(Some(_)) => println!("ok: {}", origin.path.display()),
+                    Ok(None) => {}
+                    Err(e) => {
+                        eprintln!("error: {e}");
+                        failed = true;
+                    }
+                }
+            }
+            Ok(if failed {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            })
+        }
+    }
+}
+
+/// Effective config as YAML, optionally with a comment naming where each key came from
+pub fn format_config(layered: &LayeredConfig, with_origin: bool) -> Result<String> {
+    let Value::Mapping(effective) = serde_yaml::to_value(&layered.config)? else {
+        bail!("Config did not serialize to a map");
+    };
+
+    let mut output = String::new();
+    for (key, value) in effective {
+        let mut entry = Mapping::new();
+        entry.insert(key.clone(), value);
+        let yaml = serde_yaml::to_string(&entry)?;
+        if !with_origin {
+            output.push_str(&yaml);
+            continue;
+        }
+
+        let origin = key
+            .as_str()
+            .and_then(|key| layered.origins.get(key))
+            .map(|origins| {
+                origins
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_else(|| Layer::Default.to_string());
+        let (first, rest) = yaml.split_once('\n').unwrap_or((&yaml, ""));
+        output.push_str(&format!("{first:<40} # {origin}\n{rest}"));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_matches_empty_file() {
+        let from_yaml: Config = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(Config::default().base_branch, from_yaml.base_branch);
+        assert_eq!(Config::default().base_overrides, from_yaml.base_overrides);
+        assert_eq!(Config::default().diff_against, from_yaml.diff_against);
+        assert_eq!(Config::default().theme, from_yaml.theme);
+        assert_eq!(Config::default().columns, from_yaml.columns);
+        assert_eq!(Config::default().sort, from_yaml.sort);
+        assert_eq!(Config::default().preview_window, from_yaml.preview_window);
+        assert_eq!(Config::default().height, from_yaml.height);
+        assert_eq!(
+            Config::default().preview_narrow_width,
+            from_yaml.preview_narrow_width
+        );
+        assert_eq!(Config::default().hook_timeout, from_yaml.hook_timeout);
+        assert_eq!(Config::default().worktree_dir, from_yaml.worktree_dir);
+        assert_eq!(Config::default().dir_template, from_yaml.dir_template);
+    }
+
+    #[test]
+    fn test_columns_are_parsed_in_order() {
+        let config: Config = serde_yaml::from_str("columns: [dirty, upstream, base]").unwrap();
+        assert_eq!(
+            config.columns,
+            vec![Column::Dirty, Column::Upstream, Column::Base]
+        );
+    }
+
+    #[test]
+    fn test_base_overrides_win_over_base_branch() {
+        let config: Config = serde_yaml::from_str(
+            "base_branch: origin/develop\nbase_overrides:\n  - branch: 'release/*'\n    base: origin/release\n  - branch: 'hotfix/**'\n    base: origin/main\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.configured_base_branch("release/1.2"),
+            Some("origin/release")
+        );
+        assert_eq!(
+            config.configured_base_branch("hotfix/a/b"),
+            Some("origin/main")
+        );
+        assert_eq!(
+            config.configured_base_branch("feature/x"),
+            Some("origin/develop")
+        );
+        assert_eq!(Config::default().configured_base_branch("feature/x"), None);
+    }
+
+    #[test]
+    fn test_sort_is_parsed() {
+        let config: Config = serde_yaml::from_str("sort: frecency").unwrap();
+        assert_eq!(config.sort, SortOrder::Frecency);
+    }
+
+    fn layer(dir: &Path, layer: Layer, name: &str, content: &str) -> Origin {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        Origin { layer, path }
+    }
+
+    #[test]
+    fn test_layers_are_merged_key_by_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let layers = [
+            layer(
+                dir.path(),
+                Layer::Global,
+                "global.yml",
+                "sort: frecency\ncolumns: [dirty]\nhooks:\n  post-add: echo global\n",
+            ),
+            layer(
+                dir.path(),
+                Layer::Repo,
+                "repo.yml",
+                "base_branch: origin/develop\ncolumns: [base]\nhooks:\n  pre-remove: echo repo\n",
+            ),
+            layer(
+                dir.path(),
+                Layer::Local,
+                "local.yml",
+                "columns: [upstream, dirty]\n",
+            ),
+            Origin {
+                layer: Layer::Worktree,
+                path: dir.path().join("missing.yml"),
+            },
+        ];
+
+        let layered = load_layers(&layers);
+        let config = &layered.config;
+        assert_eq!(config.sort, SortOrder::Frecency);
+        assert_eq!(config.base_branch.as_deref(), Some("origin/develop"));
+        // Lists are replaced, maps are merged
+        assert_eq!(config.columns, vec![Column::Upstream, Column::Dirty]);
+        assert_eq!(config.hooks.post_add[0].run, "echo global");
+        assert_eq!(config.hooks.pre_remove[0].run, "echo repo");
+
+        let columns: Vec<Layer> = layered.origins["columns"].iter().map(|o| o.layer).collect();
+        assert_eq!(columns, [Layer::Global, Layer::Repo, Layer::Local]);
+        assert!(!layered.origins.contains_key("hook_timeout"));
+    }
+
+    #[test]
+    fn test_invalid_layer_is_skipped_and_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let layers = [
+            layer(dir.path(), Layer::Global, "global.yml", "sort: name\n"),
+            layer(
+                dir.path(),
+                Layer::Repo,
+                "repo.yml",
+                "columns: [dirty]\nsort: sideways\n",
+            ),
+        ];
+
+        let layered = load_layers(&layers);
+        assert_eq!(layered.config.sort, SortOrder::Name);
+        assert!(layered.config.columns.is_empty());
+        assert_eq!(layered.errors.len(), 1);
+        assert_eq!(layered.errors[0].path, layers[1].path);
+        assert_eq!(layered.errors[0].location, Some((2, 7)));
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected_with_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wt.config.yml");
+
+        fs::write(&path, "columns: [dirty]\nbase_brnach: origin/develop\n").unwrap();
+        let error = read_layer(&path).unwrap_err();
+        assert_eq!(error.location, Some((2, 1)));
+        assert!(error.message.starts_with("unknown field `base_brnach`"));
+        assert_eq!(
+            error.to_string(),
+            format!("{}:2:1: {}", path.display(), error.message)
+        );
+
+        fs::write(&path, "hooks:\n  post-ad: echo hi\n").unwrap();
+        assert!(read_layer(&path).unwrap_err().message.contains("post-ad"));
+
+        fs::write(&path, "- just\n- a list\n").unwrap();
+        assert!(read_layer(&path).is_err());
+
+        fs::write(&path, "# only comments\n").unwrap();
+        assert_eq!(read_layer(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn test_format_config_names_origins() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = layer(
+            dir.path(),
+            Layer::Repo,
+            "wt.config.yml",
+            "base_branch: origin/develop\n",
+        );
+        let layered = load_layers(std::slice::from_ref(&repo));
+
+        let shown = format_config(&layered, true).unwrap();
+        let base = shown
+            .lines()
+            .find(|l| l.starts_with("base_branch:"))
+            .unwrap();
+        assert!(base.ends_with(&format!("# {repo}")));
+        let sort = shown.lines().find(|l| l.starts_with("sort:")).unwrap();
+        assert!(sort.ends_with("# default"));
+
+        // Without origins the output is plain YAML that reads back the same
+        let plain = format_config(&layered, false).unwrap();
+        let reparsed: Config = serde_yaml::from_str(&plain).unwrap();
+        assert_eq!(reparsed.base_branch.as_deref(), Some("origin/develop"));
+    }
+}
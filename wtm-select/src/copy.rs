@@ -0,0 +1,524 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A `copy:` or `symlink:` entry: a glob relative to the repository root
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "FileRuleSpec")]
+pub struct FileRule {
+    /// `*` and `?` match within a path segment, `**` matches any number of segments
+    pub pattern: String,
+    pub overwrite: Overwrite,
+    /// Destination path template; `{path}`, `{dir}`, `{name}`, `{stem}`, `{ext}` (with the
+    /// dot) and `{branch}` are replaced (default: the source path)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rename: Option<String>,
+}
+
+/// A rule is either just the glob or a map with its options
+#[derive(Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+enum FileRuleSpec {
+    Pattern(String),
+    Detailed {
+        pattern: String,
+        #[serde(default)]
+        overwrite: Overwrite,
+        #[serde(default)]
+        rename: Option<String>,
+    },
+}
+
+impl From<FileRuleSpec> for FileRule {
+    fn from(spec: FileRuleSpec) -> Self {
+        match spec {
+            FileRuleSpec::Pattern(pattern) => Self {
+                pattern,
+                overwrite: Overwrite::default(),
+                rename: None,
+            },
+            FileRuleSpec::Detailed {
+                pattern,
+                overwrite,
+                rename,
+            } => Self {
+                pattern,
+                overwrite,
+                rename,
+            },
+        }
+    }
+}
+
+/// What to do when the destination already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Overwrite {
+    /// Keep the existing file
+    #[default]
+    Never,
+    /// Replace it
+    Always,
+    /// Replace it when the source was modified more recently
+    Newer,
+}
+
+/// How a matched path is brought into the new worktree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Copy,
+    Symlink,
+}
+
+/// What applying a planned file does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Create,
+    Replace,
+    Skip(&'static str),
+}
+
+/// One file or directory to copy or link, relative to the source and destination roots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    pub action: Action,
+    pub source: PathBuf,
+    pub dest: PathBuf,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for PlannedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = match (&self.outcome, self.action) {
+            (Outcome::Skip(_), _) => "skip",
+            (_, Action::Copy) => "copy",
+            (_, Action::Symlink) => "symlink",
+        };
+        write!(f, "{verb:<8}{}", self.source.display())?;
+        if self.dest != self.source {
+            write!(f, " -> {}", self.dest.display())?;
+        }
+        match self.outcome {
+            Outcome::Create => Ok(()),
+            Outcome::Replace => write!(f, " (overwrite)"),
+            Outcome::Skip(reason) => write!(f, " ({reason})"),
+        }
+    }
+}
+
+/// Match the `copy` and `symlink` rules against `source_root` and decide what to do with
+/// each match in `dest_root`.
+///
+/// Rules are tried in order, `copy` before `symlink`; the first one matching a path wins
+/// and a matched directory is taken as a whole.
+pub fn plan(
+    copy: &[FileRule],
+    symlink: &[FileRule],
+    source_root: &Path,
+    dest_root: &Path,
+    branch: &str,
+) -> Result<Vec<PlannedFile>> {
+    let rules: Vec<(Action, &FileRule)> = copy
+        .iter()
+        .map(|rule| (Action::Copy, rule))
+        .chain(symlink.iter().map(|rule| (Action::Symlink, rule)))
+        .collect();
+    if rules.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut matches = Vec::new();
+    find_matches(source_root, &mut Vec::new(), &rules, &mut matches)?;
+
+    let mut planned: Vec<PlannedFile> = Vec::new();
+    for (segments, (action, rule)) in matches {
+        let source: PathBuf = segments.iter().collect();
+        let dest = match &rule.rename {
+            Some(template) => render_rename(template, &source, branch)?,
+            None => source.clone(),
+        };
+
+        let outcome = if planned.iter().any(|p| p.dest == dest) {
+            Outcome::Skip("already planned")
+        } else {
+            decide(
+                rule.overwrite,
+                &source_root.join(&source),
+                &dest_root.join(&dest),
+            )
+        };
+        planned.push(PlannedFile {
+            action,
+            source,
+            dest,
+            outcome,
+        });
+    }
+    Ok(planned)
+}
+
+/// Copy or link every planned file that is not skipped
+pub fn apply(planned: &[PlannedFile], source_root: &Path, dest_root: &Path) -> Result<()> {
+    for file in planned {
+        if let Outcome::Skip(_) = file.outcome {
+            continue;
+        }
+        let source = source_root.join(&file.source);
+        let dest = dest_root.join(&file.dest);
+
+        if file.outcome == Outcome::Replace {
+            remove_path(&dest)?;
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        match file.action {
+            Action::Copy => copy_recursively(&source, &dest)
+                .with_context(|| format!("Failed to copy {}", file.source.display()))?,
+            Action::Symlink => symlink(&source, &dest)
+                .with_context(|| format!("Failed to link {}", file.source.display()))?,
+        }
+    }
+    Ok(())
+}
+
+/// Report of what `apply` does, one line per file
+pub fn format_plan(planned: &[PlannedFile]) -> String {
+    planned.iter().map(|file| format!("  {file}\n")).collect()
+}
+
+fn decide(overwrite: Overwrite, source: &Path, dest: &Path) -> Outcome {
+    // symlink_metadata so that a dangling link still counts as existing
+    let Ok(dest_meta) = fs::symlink_metadata(dest) else {
+        return Outcome::Create;
+    };
+    match overwrite {
+        Overwrite::Never => Outcome::Skip("already exists"),
+        Overwrite::Always => Outcome::Replace,
+        Overwrite::Newer => {
+            let source_time = fs::metadata(source).and_then(|m| m.modified()).ok();
+            match (source_time, dest_meta.modified().ok()) {
+                (Some(source_time), Some(dest_time)) if source_time > dest_time => Outcome::Replace,
+                _ => Outcome::Skip("destination is newer"),
+            }
+        }
+    }
+}
+
+/// Walk `dir` (at `prefix` below the root) and collect paths matched by a rule.
+///
+/// Only directories some rule could match below are entered, and nested repositories
+/// or worktrees (anything with a `.git`) are never entered.
+fn find_matches<'a>(
+    dir: &Path,
+    prefix: &mut Vec<String>,
+    rules: &[(Action, &'a FileRule)],
+    matches: &mut Vec<(Vec<String>, (Action, &'a FileRule))>,
+) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .collect();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if name == ".git" {
+            continue;
+        }
+        prefix.push(name);
+
+        let segments: Vec<&str> = prefix.iter().map(String::as_str).collect();
+        if let Some(&rule) = rules
+            .iter()
+            .find(|(_, rule)| glob_match(&split(&rule.pattern), &segments))
+        {
+            matches.push((prefix.clone(), rule));
+        } else if entry.file_type().is_ok_and(|t| t.is_dir())
+            && !entry.path().join(".git").exists()
+            && rules
+                .iter()
+                .any(|(_, rule)| could_match_below(&split(&rule.pattern), &segments))
+        {
+            find_matches(&entry.path(), prefix, rules, matches)?;
+        }
+
+        prefix.pop();
+    }
+    Ok(())
+}
+
+fn split(pattern: &str) -> Vec<&str> {
+    pattern
+        .trim_start_matches("./")
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// Whether the `/`-separated `path` matches the glob `pattern`
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    glob_match(&split(pattern), &split(path))
+}
+
+/// Whether `path` matches the glob `pattern`, both split into segments
+fn glob_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| glob_match(rest, &path[skip..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path_rest)) => segment_match(segment, name) && glob_match(rest, path_rest),
+            None => false,
+        },
+    }
+}
+
+/// Whether something below the directory `dir` could match `pattern`
+fn could_match_below(pattern: &[&str], dir: &[&str]) -> bool {
+    match (pattern.split_first(), dir.split_first()) {
+        (Some((&"**", _)), _) => true,
+        (Some(_), None) => true,
+        (None, _) => false,
+        (Some((segment, rest)), Some((name, dir_rest))) => {
+            segment_match(segment, name) && could_match_below(rest, dir_rest)
+        }
+    }
+}
+
+/// `*` and `?` wildcard match of a single path segment
+fn segment_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it was tried at
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Fill in a `rename` template for `source`; the result must stay inside the worktree
+fn render_rename(template: &str, source: &Path, branch: &str) -> Result<PathBuf> {
+    let name = source.file_name().unwrap_or_default().to_string_lossy();
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = source
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let dir = source.parent().unwrap_or(Path::new("")).to_string_lossy();
+
+    let rendered = template
+        .replace("{path}", &source.to_string_lossy())
+        .replace("{dir}", &dir)
+        .replace("{name}", &name)
+        .replace("{stem}", &stem)
+        .replace("{ext}", &ext)
+        .replace("{branch}", branch);
+    let dest: PathBuf = rendered.split('/').filter(|s| !s.is_empty()).collect();
+
+    if dest.as_os_str().is_empty()
+        || dest
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        bail!(
+            "rename '{template}' for {} leaves the worktree",
+            source.display()
+        );
+    }
+    Ok(dest)
+}
+
+fn remove_path(path: &Path) -> Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+    .with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+/// Copy a file, directory tree or symlink (as a link) to `dest`
+pub fn copy_recursively(source: &Path, dest: &Path) -> Result<()> {
+    let meta = fs::symlink_metadata(source)?;
+    if meta.is_symlink() {
+        symlink(&fs::read_link(source)?, dest)?;
+    } else if meta.is_dir() {
+        fs::create_dir_all(dest)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(source, dest)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)?;
+    } else {
+        std::os::windows::fs::symlink_file(target, link)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn rules(yaml: &str) -> Vec<FileRule> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_glob_match() {
+        let matches = |pattern: &str, path: &str| {
+            glob_match(&split(pattern), &path.split('/').collect::<Vec<_>>())
+        };
+        assert!(matches(".env", ".env"));
+        assert!(matches(".env*", ".env.local"));
+        assert!(!matches(".env*", "config/.env"));
+        assert!(matches("config/*.yml", "config/app.yml"));
+        assert!(!matches("config/*.yml", "config/nested/app.yml"));
+        assert!(matches("**/.env", "apps/web/.env"));
+        assert!(matches("**/.env", ".env"));
+        assert!(matches("apps/**/*.local", "apps/a/b/x.local"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("file?.txt", "file10.txt"));
+        assert!(matches("./.claude", ".claude"));
+    }
+
+    #[test]
+    fn test_rules_accept_pattern_or_options() {
+        let parsed = rules(
+            "- .env\n- pattern: .env.example\n  overwrite: newer\n  rename: '{dir}/.env.local'\n",
+        );
+        assert_eq!(parsed[0].pattern, ".env");
+        assert_eq!(parsed[0].overwrite, Overwrite::Never);
+        assert_eq!(parsed[1].overwrite, Overwrite::Newer);
+        assert_eq!(parsed[1].rename.as_deref(), Some("{dir}/.env.local"));
+    }
+
+    #[test]
+    fn test_plan_skips_existing_and_renames() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        write(source.path(), ".env", "SECRET=1");
+        write(source.path(), ".claude/settings.json", "{}");
+        write(source.path(), "apps/web/.env.example", "X=");
+        write(source.path(), "README.md", "tracked");
+        write(dest.path(), "README.md", "tracked");
+
+        let copy = rules(
+            "- .env\n- .claude\n- README.md\n- pattern: '**/.env.example'\n  rename: '{dir}/.env'\n",
+        );
+        let planned = plan(&copy, &[], source.path(), dest.path(), "topic").unwrap();
+
+        let summary: Vec<String> = planned.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            [
+                "copy    .claude",
+                "copy    .env",
+                "skip    README.md (already exists)",
+                "copy    apps/web/.env.example -> apps/web/.env",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_copies_directories_and_links() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        write(source.path(), ".claude/settings.json", "{}");
+        write(source.path(), "node_modules/pkg/index.js", "");
+        write(source.path(), ".env", "NEW");
+        write(dest.path(), ".env", "OLD");
+
+        let copy = rules("- .claude\n- pattern: .env\n  overwrite: always\n");
+        let symlink = rules("- node_modules\n");
+        let planned = plan(&copy, &symlink, source.path(), dest.path(), "topic").unwrap();
+        assert_eq!(planned[1].outcome, Outcome::Replace);
+
+        apply(&planned, source.path(), dest.path()).unwrap();
+
+        assert!(dest.path().join(".claude/settings.json").is_file());
+        assert_eq!(fs::read_to_string(dest.path().join(".env")).unwrap(), "NEW");
+        let link = dest.path().join("node_modules");
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(
+            fs::read_link(&link).unwrap(),
+            source.path().join("node_modules")
+        );
+    }
+
+    #[test]
+    fn test_nested_worktrees_are_not_searched() {
+        let source = TempDir::new().unwrap();
+        write(source.path(), ".env", "");
+        write(source.path(), "worktrees/other/.git", "gitdir: elsewhere");
+        write(source.path(), "worktrees/other/.env", "");
+
+        let planned = plan(
+            &rules("- '**/.env'\n"),
+            &[],
+            source.path(),
+            Path::new("/nonexistent"),
+            "x",
+        )
+        .unwrap();
+        assert_eq!(planned.len(), 1);
+        assert_eq!(planned[0].source, PathBuf::from(".env"));
+    }
+
+    #[test]
+    fn test_rename_must_stay_inside_worktree() {
+        let err = render_rename("../{name}", Path::new(".env"), "x").unwrap_err();
+        assert!(err.to_string().contains("leaves the worktree"));
+        assert_eq!(
+            render_rename("{stem}.{branch}{ext}", Path::new("a/b.txt"), "topic").unwrap(),
+            PathBuf::from("b.topic.txt")
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::cache::write_atomically;
+use anyhow::{Context, Result};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// History file, relative to the common git dir
+const HISTORY_FILE: &str = "wtm-history.json";
+
+/// Visits kept per worktree; older ones no longer affect the score
+const MAX_VISITS: usize = 20;
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+
+/// Worktrees picked with `select`, used for the `frecency` sort
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    #[serde(skip)]
+    file: PathBuf,
+    /// Worktree path -> unix timestamps of the most recent visits, oldest first
+    visits: HashMap<String, Vec<i64>>,
+}
+
+impl History {
+    /// Load the history of the repository owning `repo`; a missing or unreadable file is empty
+    pub fn load(repo: &Repository) -> Self {
+        let file = repo.commondir().join(HISTORY_FILE);
+        let history = fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| serde_json::from_str::<History>(&content).ok())
+            .unwrap_or_default();
+        Self { file, ..history }
+    }
+
+    /// Remember that `path` was picked at `now`
+    pub fn record(&mut self, path: &str, now: i64) {
+        let visits = self.visits.entry(path.to_string()).or_default();
+        visits.push(now);
+        if visits.len() > MAX_VISITS {
+            visits.drain(..visits.len() - MAX_VISITS);
+        }
+    }
+
+    /// Drop worktrees that no longer exist
+    pub fn retain(&mut self, keep: &[String]) {
+        self.visits.retain(|path, _| keep.contains(path));
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string(self)?;
+        write_atomically(&self.file, &content).context("Failed to write selection history")
+    }
+
+    /// Frecency of `path`: every visit counts, recent ones count more
+    pub fn score(&self, path: &str, now: i64) -> f64 {
+        self.visits.get(path).map_or(0.0, |visits| {
+            visits
+                .iter()
+                .map(|&visit| match now - visit {
+                    age if age < HOUR => 4.0,
+                    age if age < DAY => 2.0,
+                    age if age < WEEK => 1.0,
+                    _ => 0.25,
+                })
+                .sum()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_visits_outweigh_old_ones() {
+        let now = 1_000_000_000;
+        let mut history = History::default();
+        for _ in 0..3 {
+            history.record("/old", now - 30 * DAY);
+        }
+        history.record("/recent", now - 10);
+
+        assert!(history.score("/recent", now) > history.score("/old", now));
+        assert_eq!(history.score("/never", now), 0.0);
+    }
+
+    #[test]
+    fn test_only_latest_visits_are_kept() {
+        let mut history = History::default();
+        for visit in 0..(MAX_VISITS as i64 + 5) {
+            history.record("/wt", visit);
+        }
+
+        let visits = &history.visits["/wt"];
+        assert_eq!(visits.len(), MAX_VISITS);
+        assert_eq!(visits[0], 5);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        let mut history = History::load(&repo);
+        history.record("/a", 100);
+        history.record("/b", 200);
+        history.retain(&["/a".to_string()]);
+        history.save().unwrap();
+
+        let loaded = History::load(&repo);
+        assert_eq!(loaded.visits.get("/a"), Some(&vec![100]));
+        assert!(!loaded.visits.contains_key("/b"));
+    }
+}
@@ -0,0 +1,418 @@
+use crate::config::{Config, load_config};
+use crate::theme::warning_prefix;
+use crate::worktree::{FALLBACK_BASE_BRANCH, main_worktree_dir, open_main_repo};
+use anyhow::{Context, Result, bail};
+use git2::Repository;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default `hook_timeout`, in seconds
+pub const DEFAULT_HOOK_TIMEOUT: u64 = 300;
+
+/// How often a running hook is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Point in a worktree's lifecycle at which hooks run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreAdd,
+    PostAdd,
+    PostMove,
+    PreRemove,
+    PostRemove,
+    PostSwitch,
+}
+
+impl HookEvent {
+    /// Name used in wt.config.yml and `WT_EVENT`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PreAdd => "pre-add",
+            Self::PostAdd => "post-add",
+            Self::PostMove => "post-move",
+            Self::PreRemove => "pre-remove",
+            Self::PostRemove => "post-remove",
+            Self::PostSwitch => "post-switch",
+        }
+    }
+
+    /// A failing `pre-*` hook cancels the operation; later hooks only warn by default
+    fn default_on_failure(self) -> OnFailure {
+        match self {
+            Self::PreAdd | Self::PreRemove => OnFailure::Abort,
+            _ => OnFailure::Warn,
+        }
+    }
+}
+
+impl fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// What to do when a hook exits non-zero or times out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    /// Stop and report an error (before the operation for `pre-*` hooks)
+    Abort,
+    /// Print a warning and carry on
+    Warn,
+}
+
+/// Hooks per event, as configured under `hooks:`
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct HooksConfig {
+    #[serde(deserialize_with = "one_or_many")]
+    pub pre_add: Vec<Hook>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub post_add: Vec<Hook>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub post_move: Vec<Hook>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub pre_remove: Vec<Hook>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub post_remove: Vec<Hook>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub post_switch: Vec<Hook>,
+}
+
+impl HooksConfig {
+    pub fn for_event(&self, event: HookEvent) -> &[Hook] {
+        match event {
+            HookEvent::PreAdd => &self.pre_add,
+            HookEvent::PostAdd => &self.post_add,
+            HookEvent::PostMove => &self.post_move,
+            HookEvent::PreRemove => &self.pre_remove,
+            HookEvent::PostRemove => &self.post_remove,
+            HookEvent::PostSwitch => &self.post_switch,
+        }
+    }
+}
+
+/// A shell command, or a script path relative to the project root
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "HookSpec")]
+pub struct Hook {
+    pub run: String,
+    /// Seconds before the hook is killed (default: `hook_timeout`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Default: `abort` for `pre-*` hooks, `warn` otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<OnFailure>,
+}
+
+/// A hook is either just the command or a map with its options
+#[derive(Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+enum HookSpec {
+    Run(String),
+    Detailed {
+        run: String,
+        #[serde(default)]
+        timeout: Option<u64>,
+        #[serde(default)]
+        on_failure: Option<OnFailure>,
+    },
+}
+
+impl From<HookSpec> for Hook {
+    fn from(spec: HookSpec) -> Self {
+        match spec {
+            HookSpec::Run(run) => Self {
+                run,
+                timeout: None,
+                on_failure: None,
+            },
+            HookSpec::Detailed {
+                run,
+                timeout,
+                on_failure,
+            } => Self {
+                run,
+                timeout,
+                on_failure,
+            },
+        }
+    }
+}
+
+/// Accept a single hook where a list is expected
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Hook>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Hook),
+        Many(Vec<Hook>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(hook) => vec![hook],
+        OneOrMany::Many(hooks) => hooks,
+    })
+}
+
+/// Runs the configured hooks of a repository
+#[derive(Debug)]
+pub struct HookRunner {
+    project_root: PathBuf,
+    config: Config,
+}
+
+impl HookRunner {
+    pub fn new(project_root: PathBuf, config: Config) -> Self {
+        Self {
+            project_root,
+            config,
+        }
+    }
+
+    /// Hooks from wt.config.yml in the main worktree of the repository owning `repo`
+    pub fn for_repo(repo: &Repository) -> Result<Self> {
+        let main_repo = open_main_repo(repo)?;
+        // Bare repositories have no checkout to read the config from
+        let project_root =
+            main_worktree_dir(&main_repo).unwrap_or_else(|| main_repo.path().to_path_buf());
+        let config = load_config(&project_root);
+        Ok(Self::new(project_root, config))
+    }
+
+    /// Root of the main worktree, where hooks and file rules are resolved from
+    pub fn project_root(&self) -> &Path {
+        &self.project_root
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Run every hook for `event`, in order.
+    ///
+    /// Fails when a hook with the `abort` policy exits non-zero or times out; the
+    /// remaining hooks are skipped.
+    pub fn run(&self, event: HookEvent, worktree_path: &Path, branch: &str) -> Result<()> {
+        for hook in self.config.hooks.for_event(event) {
+            let Err(e) = self.run_hook(hook, event, worktree_path, branch) else {
+                continue;
+            };
+            match hook.on_failure.unwrap_or(event.default_on_failure()) {
+                OnFailure::Abort => return Err(e.context(format!("{event} hook failed"))),
+                OnFailure::Warn => eprintln!("{}{event} hook failed: {e:#}", warning_prefix()),
+            }
+        }
+        Ok(())
+    }
+
+    fn run_hook(
+        &self,
+        hook: &Hook,
+        event: HookEvent,
+        worktree_path: &Path,
+        branch: &str,
+    ) -> Result<()> {
+        // A script path runs directly; anything else is a shell command
+        let script = self.project_root.join(&hook.run);
+        let mut command = if !hook.run.contains(char::is_whitespace) && script.is_file() {
+            Command::new(script)
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(&hook.run);
+            command
+        };
+
+        // `pre-add` and `post-remove` run before the worktree exists or after it is gone
+        let cwd = if worktree_path.is_dir() {
+            worktree_path
+        } else {
+            &self.project_root
+        };
+
+        let base_branch = match Repository::open(&self.project_root) {
+            Ok(repo) => self.config.base_branch_for(&repo, branch),
+            Err(_) => self
+                .config
+                .configured_base_branch(branch)
+                .unwrap_or(FALLBACK_BASE_BRANCH)
+                .to_string(),
+        };
+
+        // stdout is reserved for the path printed to the shell. The hook gets a process
+        // group of its own, so a timeout also stops whatever it started.
+        in_own_process_group(&mut command);
+        let mut child = command
+            .current_dir(cwd)
+            .env("WT_WORKTREE_PATH", worktree_path)
+            .env("WT_BRANCH_NAME", branch)
+            .env("WT_PROJECT_ROOT", &self.project_root)
+            .env("WT_BASE_BRANCH", base_branch)
+            .env("WT_EVENT", event.name())
+            .stdout(Stdio::from(io::stderr()))
+            .spawn()
+            .with_context(|| format!("Failed to run `{}`", hook.run))?;
+
+        let timeout = Duration::from_secs(hook.timeout.unwrap_or(self.config.hook_timeout));
+        let started = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait()? {
+                if !status.success() {
+                    bail!("`{}` {status}", hook.run);
+                }
+                return Ok(());
+            }
+            if started.elapsed() >= timeout {
+                kill_process_group(&mut child);
+                let _ = child.wait();
+                bail!("`{}` timed out after {}s", hook.run, timeout.as_secs());
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn in_own_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn in_own_process_group(_command: &mut Command) {}
+
+/// Kill `child` and every process in the group it leads
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    let Ok(pid) = libc::pid_t::try_from(child.id()) else {
+        let _ = child.kill();
+        return;
+    };
+    // SAFETY: kill(2) takes no pointers; a negative pid names the child's own group
+    if unsafe { libc::kill(-pid, libc::SIGKILL) } != 0 {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(windows)]
+fn kill_process_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn runner(yaml: &str) -> (TempDir, HookRunner) {
+        let dir = TempDir::new().unwrap();
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let runner = HookRunner::new(dir.path().to_path_buf(), config);
+        (dir, runner)
+    }
+
+    #[test]
+    fn test_hooks_accept_command_list_and_options() {
+        let config: Config = serde_yaml::from_str(
+            "hooks:\n  post-add: npm install\n  pre-remove:\n    - docker compose down -v\n    - run: scripts/cleanup.sh\n      timeout: 10\n      on_failure: warn\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.hooks.post_add.len(), 1);
+        assert_eq!(config.hooks.post_add[0].run, "npm install");
+        let cleanup = &config.hooks.pre_remove[1];
+        assert_eq!(cleanup.run, "scripts/cleanup.sh");
+        assert_eq!(cleanup.timeout, Some(10));
+        assert_eq!(cleanup.on_failure, Some(OnFailure::Warn));
+        assert!(config.hooks.post_switch.is_empty());
+    }
+
+    #[test]
+    fn test_hook_sees_environment() {
+        let (dir, runner) = runner(
+            "base_branch: origin/develop\nhooks:\n  post-add: env | grep ^WT_ | sort > env.txt\n",
+        );
+        let worktree = dir.path().join("wt");
+        fs::create_dir(&worktree).unwrap();
+
+        runner
+            .run(HookEvent::PostAdd, &worktree, "feature/x")
+            .unwrap();
+
+        let env = fs::read_to_string(worktree.join("env.txt")).unwrap();
+        assert!(env.contains("WT_BASE_BRANCH=origin/develop\n"));
+        assert!(env.contains("WT_BRANCH_NAME=feature/x\n"));
+        assert!(env.contains("WT_EVENT=post-add\n"));
+        assert!(env.contains(&format!("WT_PROJECT_ROOT={}\n", dir.path().display())));
+        assert!(env.contains(&format!("WT_WORKTREE_PATH={}\n", worktree.display())));
+    }
+
+    #[test]
+    fn test_script_path_is_resolved_from_project_root() {
+        let (dir, runner) = runner("hooks:\n  pre-add: setup.sh\n");
+        fs::write(dir.path().join("setup.sh"), "#!/bin/sh\ntouch ran\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(
+                dir.path().join("setup.sh"),
+                fs::Permissions::from_mode(0o755),
+            )
+            .unwrap();
+        }
+
+        // The worktree does not exist yet, so the hook runs in the project root
+        runner
+            .run(HookEvent::PreAdd, &dir.path().join("wt"), "x")
+            .unwrap();
+        assert!(dir.path().join("ran").exists());
+    }
+
+    #[test]
+    fn test_failing_pre_hook_aborts_and_post_hook_warns() {
+        let (dir, runner) = runner(
+            "hooks:\n  pre-remove: [\"exit 3\", \"touch skipped\"]\n  post-remove: [\"exit 1\", \"touch ran\"]\n",
+        );
+
+        let err = runner
+            .run(HookEvent::PreRemove, dir.path(), "x")
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("pre-remove hook failed"));
+        assert!(!dir.path().join("skipped").exists());
+
+        runner.run(HookEvent::PostRemove, dir.path(), "x").unwrap();
+        assert!(dir.path().join("ran").exists());
+    }
+
+    #[test]
+    fn test_hook_is_killed_after_timeout() {
+        let (dir, runner) = runner(
+            "hooks:\n  post-switch:\n    - run: sleep 5\n      timeout: 0\n      on_failure: abort\n",
+        );
+
+        let started = Instant::now();
+        let err = runner
+            .run(HookEvent::PostSwitch, dir.path(), "x")
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_kills_processes_started_by_the_hook() {
+        let (dir, runner) = runner(
+            "hooks:\n  post-switch:\n    - run: (sleep 2; touch survived) & wait\n      timeout: 1\n      on_failure: abort\n",
+        );
+
+        assert!(runner.run(HookEvent::PostSwitch, dir.path(), "x").is_err());
+        thread::sleep(Duration::from_secs(2));
+        assert!(!dir.path().join("survived").exists());
+    }
+}
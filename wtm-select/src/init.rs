@@ -0,0 +1,168 @@
+use crate::add::worktree_path;
+use crate::config::Config;
+use crate::hooks::HookRunner;
+use crate::worktree::open_current_repo;
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::fs;
+use std::path::{Component, Path};
+
+/// Template written by `wtm-select init`
+const HOOK_TEMPLATE: &str = r#"#!/bin/zsh
+# .wt_hook.zsh - run after `wt add`
+# Available variables: $WT_WORKTREE_PATH, $WT_BRANCH_NAME, $WT_PROJECT_ROOT
+
+echo "🌲 Setting up worktree for branch: $WT_BRANCH_NAME"
+
+# Copy common files from main repository
+# (or list them under `copy:` / `symlink:` in wt.config.yml and drop this loop)
+copy_items=(".env" ".claude" ".env.local")
+for item in "${copy_items[@]}"; do
+    src="${WT_PROJECT_ROOT}/$item"
+    dest="${WT_WORKTREE_PATH}/$item"
+
+    [[ -e "$src" ]] || continue
+    if [[ -e "$dest" ]]; then
+        echo "  Skipped $item (already exists)"
+        continue
+    fi
+
+    cp -R "$src" "$dest" && echo "  Copied $item"
+done
+
+# Example: Install dependencies
+# [[ -f package.json ]] && npm install
+
+# Example: Run setup script
+# [[ -x ./setup.sh ]] && ./setup.sh
+"#;
+
+/// Entry point for the `init` subcommand
+pub fn run() -> Result<()> {
+    let repo = open_current_repo()?;
+
+    // info/exclude is only read from the common dir, even inside a linked worktree
+    let exclude_file = repo.commondir().join("info").join("exclude");
+    let hooks = HookRunner::for_repo(&repo)?;
+    match excluded_worktree_dir(hooks.project_root(), hooks.config())? {
+        Some(entry) => add_exclude_entry(&exclude_file, &entry)?,
+        None => println!("worktree_dir is outside the repository; nothing to exclude"),
+    }
+
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow::anyhow!("Cannot initialize a bare repository"))?;
+    write_hook_template(&workdir.join(".wt_hook.zsh"))
+}
+
+/// Top-level directory new worktrees are created under, when it is inside the repository
+fn excluded_worktree_dir(project_root: &Path, config: &Config) -> Result<Option<String>> {
+    let sample = worktree_path(project_root, config, "branch", Local::now())?;
+    let Ok(relative) = sample.strip_prefix(project_root) else {
+        return Ok(None);
+    };
+
+    // Without a directory between the root and the worktree there is nothing safe to exclude
+    let mut components = relative.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(top)), Some(_)) => Ok(Some(top.to_string_lossy().into_owned())),
+        _ => Ok(None),
+    }
+}
+
+/// Append `entry` to the exclude file unless it is already listed
+fn add_exclude_entry(exclude_file: &Path, entry: &str) -> Result<()> {
+    if exclude_file.exists() {
+        let content = fs::read_to_string(exclude_file)
+            .with_context(|| format!("Failed to read {}", exclude_file.display()))?;
+        if content.lines().any(|line| line == entry) {
+            println!("'{entry}' already in .git/info/exclude");
+            return Ok(());
+        }
+
+        let separator = if content.is_empty() || content.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        fs::write(exclude_file, format!("{content}{separator}{entry}\n"))
+            .with_context(|| format!("Failed to write {}", exclude_file.display()))?;
+        println!("Added '{entry}' to .git/info/exclude");
+    } else {
+        if let Some(parent) = exclude_file.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(exclude_file, format!("{entry}\n"))
+            .with_context(|| format!("Failed to write {}", exclude_file.display()))?;
+        println!("Created .git/info/exclude and added '{entry}'");
+    }
+    Ok(())
+}
+
+/// Create the hook template if it does not exist yet
+fn write_hook_template(hook_path: &Path) -> Result<()> {
+    if hook_path.exists() {
+        println!(".wt_hook.zsh already exists");
+        return Ok(());
+    }
+
+    fs::write(hook_path, HOOK_TEMPLATE)
+        .with_context(|| format!("Failed to write {}", hook_path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(hook_path, fs::Permissions::from_mode(0o755))
+            .context("Failed to make .wt_hook.zsh executable")?;
+    }
+
+    println!("Created .wt_hook.zsh template");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_add_exclude_entry_is_idempotent() {
+        let dir = TempDir::new().unwrap();
+        let exclude = dir.path().join("info").join("exclude");
+
+        add_exclude_entry(&exclude, "worktrees").unwrap();
+        fs::write(&exclude, "*.log\nworktrees").unwrap();
+        add_exclude_entry(&exclude, "worktrees").unwrap();
+
+        assert_eq!(fs::read_to_string(&exclude).unwrap(), "*.log\nworktrees");
+    }
+
+    #[test]
+    fn test_add_exclude_entry_appends_newline() {
+        let dir = TempDir::new().unwrap();
+        let exclude = dir.path().join("exclude");
+        fs::write(&exclude, "*.log").unwrap();
+
+        add_exclude_entry(&exclude, "worktrees").unwrap();
+
+        assert_eq!(fs::read_to_string(&exclude).unwrap(), "*.log\nworktrees\n");
+    }
+
+    #[test]
+    fn test_excluded_worktree_dir_follows_config() {
+        let root = Path::new("/src/wtm");
+        let excluded = |yaml: &str| {
+            let config: Config = serde_yaml::from_str(yaml).unwrap();
+            excluded_worktree_dir(root, &config).unwrap()
+        };
+
+        assert_eq!(excluded("{}"), Some("worktrees".to_string()));
+        assert_eq!(
+            excluded("worktree_dir: .wt/{user}"),
+            Some(".wt".to_string())
+        );
+        assert_eq!(excluded("worktree_dir: ../{repo}.worktrees"), None);
+        assert_eq!(excluded("worktree_dir: ."), None);
+    }
+}
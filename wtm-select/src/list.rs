@@ -0,0 +1,274 @@
+use crate::cache::MetadataCache;
+use crate::config::load_config;
+use crate::worktree::{
+    FALLBACK_BASE_BRANCH, WorktreeEntry, collect_worktrees, get_dirname, open_current_repo,
+};
+use anyhow::Result;
+use clap::{Args as ClapArgs, ValueEnum};
+use git2::Repository;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::Path;
+
+/// Arguments for `wtm-select list`
+#[derive(ClapArgs, Debug)]
+pub struct ListArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+    pub format: ListFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    /// Human readable, like `git worktree list`
+    Table,
+    /// JSON array of objects
+    Json,
+    /// Tab separated: branch, path, dirname, head, last_commit, dirty, ahead, behind, state, state_detail
+    Tsv,
+    /// Stanzas of `key value` lines separated by a blank line, like `git worktree list --porcelain`
+    Porcelain,
+}
+
+/// Everything `list` knows about a single worktree
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct WorktreeInfo {
+    pub branch: String,
+    pub path: String,
+    pub dirname: String,
+    /// branch, detached, locked, prunable, missing or bare
+    pub state: String,
+    /// Detached commit or lock reason
+    pub state_detail: Option<String>,
+    /// Full HEAD commit id
+    pub head: Option<String>,
+    /// Unix timestamp of the HEAD commit
+    pub last_commit: Option<i64>,
+    /// Number of changed and untracked files
+    pub dirty: usize,
+    /// Base branch used for `ahead`/`behind`
+    pub base_branch: String,
+    /// Commits on HEAD that are not on the base branch
+    pub ahead: Option<usize>,
+    /// Commits on the base branch that are not on HEAD
+    pub behind: Option<usize>,
+}
+
+/// Entry point for the `list` subcommand
+pub fn run(args: ListArgs) -> Result<()> {
+    let cache = MetadataCache::load(&open_current_repo()?);
+    let infos: Vec<WorktreeInfo> = collect_worktrees()?
+        .into_par_iter()
+        .map(|entry| collect_info(entry, &cache))
+        .collect();
+
+    print!("{}", render(&infos, args.format)?);
+
+    // `list` sees every worktree, so it is a good time to forget removed ones
+    let paths: Vec<String> = infos.into_iter().map(|info| info.path).collect();
+    let _ = cache.save(Some(&paths));
+    Ok(())
+}
+
+/// Gather HEAD, dirty count and ahead/behind for one worktree
+pub fn collect_info(entry: WorktreeEntry, cache: &MetadataCache) -> WorktreeInfo {
+    let config = load_config(Path::new(&entry.path));
+    let repo = Repository::open(&entry.path).ok();
+    let base_branch = match &repo {
+        Some(repo) => config.base_branch_for(repo, &entry.branch),
+        None => config
+            .configured_base_branch(&entry.branch)
+            .unwrap_or(FALLBACK_BASE_BRANCH)
+            .to_string(),
+    };
+    let mut info = WorktreeInfo {
+        dirname: get_dirname(&entry.path),
+        state: entry.state.kind().to_string(),
+        state_detail: entry.state.detail().map(str::to_string),
+        branch: entry.branch,
+        path: entry.path,
+        head: None,
+        last_commit: None,
+        dirty: 0,
+        base_branch,
+        ahead: None,
+        behind: None,
+    };
+
+    let Some(repo) = repo else {
+        return info;
+    };
+
+    if let Ok(head_commit) = repo.head().and_then(|h| h.peel_to_commit()) {
+        info.head = Some(head_commit.id().to_string());
+        info.last_commit = Some(head_commit.time().seconds());
+    }
+    // Unlike the picker columns, the count is acted on, so the status is always rescanned
+    if let Some(metadata) = cache.get_fresh(&info.path, &info.base_branch) {
+        info.dirty = metadata.dirty;
+        if let Some(base) = metadata.base {
+            info.ahead = Some(base.ahead);
+            info.behind = Some(base.behind);
+        }
+    }
+
+    info
+}
+
+/// Render worktree infos in the requested format
+pub fn render(infos: &[WorktreeInfo], format: ListFormat) -> Result<String> {
+    let mut output = String::new();
+
+    match format {
+        ListFormat::Table => {
+            let width = infos.iter().map(|i| i.path.len()).max().unwrap_or(0);
+            for info in infos {
+                let head = info.head.as_deref().map_or("0000000", |h| &h[..7]);
+                output.push_str(&format!("{:<width$} {head} [{}]", info.path, info.branch));
+                if info.state != "branch" && info.state != "detached" {
+                    output.push_str(&format!(" {}", info.state));
+                }
+                output.push('\n');
+            }
+        }
+        ListFormat::Json => {
+            output.push_str(&serde_json::to_string_pretty(infos)?);
+            output.push('\n');
+        }
+        ListFormat::Tsv => {
+            for info in infos {
+                let fields = [
+                    info.branch.clone(),
+                    info.path.clone(),
+                    info.dirname.clone(),
+                    info.head.clone().unwrap_or_default(),
+                    opt_to_string(info.last_commit),
+                    info.dirty.to_string(),
+                    opt_to_string(info.ahead),
+                    opt_to_string(info.behind),
+                    info.state.clone(),
+                    info.state_detail.clone().unwrap_or_default(),
+                ];
+                output.push_str(&fields.join("\t"));
+                output.push('\n');
+            }
+        }
+        ListFormat::Porcelain => {
+            for info in infos {
+                output.push_str(&format!("worktree {}\n", info.path));
+                if let Some(head) = &info.head {
+                    output.push_str(&format!("HEAD {head}\n"));
+                }
+                output.push_str(&format!("branch {}\n", info.branch));
+                output.push_str(&format!("dirname {}\n", info.dirname));
+                output.push_str(&format!("state {}\n", info.state));
+                if let Some(detail) = &info.state_detail {
+                    output.push_str(&format!("state-detail {detail}\n"));
+                }
+                if let Some(ts) = info.last_commit {
+                    output.push_str(&format!("last-commit {ts}\n"));
+                }
+                output.push_str(&format!("dirty {}\n", info.dirty));
+                if let (Some(ahead), Some(behind)) = (info.ahead, info.behind) {
+                    output.push_str(&format!(
+                        "ahead-behind {ahead} {behind} {}\n",
+                        info.base_branch
+                    ));
+                }
+                output.push('\n');
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+fn opt_to_string<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worktree::WorktreeState;
+
+    fn sample() -> WorktreeInfo {
+        WorktreeInfo {
+            branch: "feature/x".to_string(),
+            path: "/repo/worktrees/20240101_000000_feature_x".to_string(),
+            dirname: "20240101_000000_feature_x".to_string(),
+            state: "locked".to_string(),
+            state_detail: Some("on usb drive".to_string()),
+            head: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+            last_commit: Some(1_700_000_000),
+            dirty: 3,
+            base_branch: "origin/main".to_string(),
+            ahead: Some(2),
+            behind: None,
+        }
+    }
+
+    #[test]
+    fn test_dirty_count_sees_edits_the_cache_key_misses() {
+        let (dir, repo) = crate::add::tests::init_repo();
+        let file = dir.path().join("tracked.txt");
+        std::fs::write(&file, "one").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("tracked.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("test", "test@example.com").unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "tracked", &tree, &[&parent])
+            .unwrap();
+
+        let path = dir.path().to_str().unwrap();
+        let cache = MetadataCache::load(&repo);
+        assert_eq!(cache.get(path, "origin/main").unwrap().dirty, 0);
+        std::fs::write(&file, "two").unwrap();
+
+        let entry = WorktreeEntry {
+            branch: "main".to_string(),
+            path: path.to_string(),
+            state: WorktreeState::Branch,
+            is_main: true,
+            is_current: false,
+            operation: None,
+            created: None,
+        };
+        assert_eq!(collect_info(entry, &cache).dirty, 1);
+    }
+
+    #[test]
+    fn test_render_tsv_keeps_empty_columns() {
+        let output = render(&[sample()], ListFormat::Tsv).unwrap();
+        let fields: Vec<&str> = output.trim_end_matches('\n').split('\t').collect();
+        assert_eq!(fields.len(), 10);
+        assert_eq!(fields[0], "feature/x");
+        assert_eq!(fields[4], "1700000000");
+        assert_eq!(fields[5], "3");
+        assert_eq!(fields[6], "2");
+        assert_eq!(fields[7], "");
+        assert_eq!(fields[8], "locked");
+        assert_eq!(fields[9], "on usb drive");
+    }
+
+    #[test]
+    fn test_render_json_round_trips() {
+        let output = render(&[sample()], ListFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[0]["branch"], "feature/x");
+        assert_eq!(value[0]["dirty"], 3);
+        assert!(value[0]["behind"].is_null());
+    }
+
+    #[test]
+    fn test_render_porcelain_stanzas() {
+        let output = render(&[sample(), sample()], ListFormat::Porcelain).unwrap();
+        let stanzas: Vec<&str> = output.split("\n\n").filter(|s| !s.is_empty()).collect();
+        assert_eq!(stanzas.len(), 2);
+        assert!(stanzas[0].starts_with("worktree /repo/worktrees/"));
+        // ahead-behind is only emitted when both sides are known
+        assert!(!stanzas[0].contains("ahead-behind"));
+    }
+}
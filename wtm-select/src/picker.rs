@@ -0,0 +1,964 @@
+use crate::add::dir_name_timestamp;
+use crate::cache::MetadataCache;
+use crate::config::{Column, SortOrder, load_config, load_layered_config};
+use crate::history::History;
+use crate::preview::{
+    PREVIEW_MODE_KEY, PreviewMode, PreviewModeCell, generate_diff_preview, generate_preview,
+};
+use crate::stash::{Stash, list_stashes, stashes_for_branch};
+use crate::theme::{Theme, set_no_emoji};
+use crate::worktree::{
+    WorktreeEntry, WorktreeState, checkout_state, collect_worktrees, collect_worktrees_quick,
+    get_dirname, head_branch_name, head_commit_time, main_worktree_dir, open_current_repo,
+    open_main_repo, relative_time, upstream_ahead_behind,
+};
+use anyhow::Result;
+use chrono::Local;
+use clap::Args as ClapArgs;
+use git2::Repository;
+use ratatui::crossterm::terminal;
+use ratatui::text::Line;
+use rayon::prelude::*;
+use skim::FuzzyAlgorithm;
+use skim::binds::parse_key;
+use skim::prelude::*;
+use skim::tui::event::ActionCallback;
+use skim::tui::layout::LayoutTemplate;
+use skim::tui::options::PreviewLayout;
+use skim::tui::{App, Direction, Size};
+use skim::{DisplayContext, Matches};
+use std::borrow::Cow;
+use std::cmp;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::thread;
+
+/// Key that shows or hides the preview
+pub const PREVIEW_TOGGLE_KEY: &str = "alt-p";
+
+/// Key that moves the preview around the list
+pub const PREVIEW_ROTATE_KEY: &str = "alt-r";
+
+/// Height of a preview moved below the list on a narrow terminal
+const NARROW_PREVIEW_PERCENT: u16 = 50;
+
+/// Marks rows of worktrees in the middle of an operation or with stashes
+const WARNING_MARKER: &str = "⚠";
+
+/// `WARNING_MARKER` in ascii mode
+const ASCII_WARNING_MARKER: &str = "!";
+
+#[derive(Debug)]
+pub struct WorktreeItem {
+    pub branch: String,
+    pub path: String,
+    /// Search text (updated, branch, state, directory). Only the fixed-width state column
+    /// changes after the item is sent to skim, so marks and match ranges stay valid.
+    display_text: RwLock<String>,
+    matching_ranges: Vec<(usize, usize)>,
+    /// Byte offset of the state column
+    badge_start: usize,
+    /// Byte offset of the directory column; optional columns are shown right before it
+    dirname_start: usize,
+    columns: Vec<Column>,
+    /// Values for `columns`, filled in by the background pass
+    column_values: OnceLock<Vec<String>>,
+    /// The main repository checkout
+    pub is_main: bool,
+    /// The worktree containing the current working directory
+    pub is_current: bool,
+    /// Updated by the background pass once the worktree directory has been checked
+    state: RwLock<WorktreeState>,
+    /// What the preview shows, shared by all items of one picker
+    pub preview_mode: Arc<PreviewModeCell>,
+    pub theme: Arc<Theme>,
+}
+
+impl WorktreeItem {
+    pub fn new(
+        branch: String,
+        path: String,
+        dirname: String,
+        updated_relative: String,
+        state: WorktreeState,
+        columns: &[Column],
+    ) -> Self {
+        // Build the display string once so `text()` and highlighting stay consistent.
+        let updated_col = format!("{updated_relative:<10}");
+        let branch_col = format!("{branch:<40}");
+        let badge_col = format!("{:<8}", state.badge());
+        let display_text = format!("{updated_col} {branch_col} {badge_col} {dirname}");
+
+        // Describe the byte ranges we want skim to match against.
+        // This allows ^prefix to anchor to the branch/dirname columns instead of the first column.
+        let updated_range = (0, updated_col.len());
+        let branch_start = updated_col.len() + 1;
+        let branch_range = (branch_start, branch_start + branch.len());
+        let badge_start = branch_start + branch_col.len() + 1;
+        let badge_range = (badge_start, badge_start + badge_col.len());
+        let dirname_start = badge_start + badge_col.len() + 1;
+        let dirname_range = (dirname_start, display_text.len());
+
+        Self {
+            branch,
+            path,
+            display_text: RwLock::new(display_text),
+            matching_ranges: vec![updated_range, branch_range, badge_range, dirname_range],
+            badge_start,
+            dirname_start,
+            columns: columns.to_vec(),
+            column_values: OnceLock::new(),
+            is_main: false,
+            is_current: false,
+            state: RwLock::new(state),
+            preview_mode: Arc::default(),
+            theme: Arc::default(),
+        }
+    }
+
+    /// Flag the row with `WARNING_MARKER` and `note` after the directory, outside the
+    /// searched ranges
+    pub fn with_warning(self, note: &str) -> Self {
+        let marker = self.theme.symbol(WARNING_MARKER, ASCII_WARNING_MARKER);
+        self.display_text
+            .write()
+            .unwrap()
+            .push_str(&format!(" {marker} {note}"));
+        self
+    }
+
+    pub fn state(&self) -> WorktreeState {
+        self.state.read().unwrap().clone()
+    }
+
+    /// Replace the state and its badge; every badge fits the 8-wide column
+    pub fn set_state(&self, state: WorktreeState) {
+        let badge_col = format!("{:<8}", state.badge());
+        let range = self.badge_start..self.badge_start + badge_col.len();
+        self.display_text
+            .write()
+            .unwrap()
+            .replace_range(range, &badge_col);
+        *self.state.write().unwrap() = state;
+    }
+
+    /// Fill in the optional columns; later calls are ignored
+    pub fn set_column_values(&self, values: Vec<String>) {
+        let _ = self.column_values.set(values);
+    }
+
+    /// Optional columns as shown in the row, `…` while still being computed
+    fn extra_columns(&self) -> String {
+        let values = self.column_values.get();
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, &column)| {
+                let text = values.and_then(|v| v.get(i)).map_or("…", String::as_str);
+                format!("{text:<width$} ", width = column_width(column))
+            })
+            .collect()
+    }
+}
+
+/// Header label of an optional column
+fn column_label(column: Column) -> &'static str {
+    match column {
+        Column::Upstream => "Upstream",
+        Column::Base => "Base",
+        Column::Dirty => "Dirty",
+    }
+}
+
+/// Display width of an optional column
+fn column_width(column: Column) -> usize {
+    match column {
+        Column::Upstream | Column::Base => 9,
+        Column::Dirty => 5,
+    }
+}
+
+/// `↑2↓1`, `=` when in sync, `-` when there is nothing to compare against
+fn format_ahead_behind(counts: Option<(usize, usize)>) -> String {
+    match counts {
+        None => "-".to_string(),
+        Some((0, 0)) => "=".to_string(),
+        Some((ahead, behind)) => {
+            let mut text = String::new();
+            if ahead > 0 {
+                text.push_str(&format!("↑{ahead}"));
+            }
+            if behind > 0 {
+                text.push_str(&format!("↓{behind}"));
+            }
+            text
+        }
+    }
+}
+
+/// Compute the configured columns for one worktree
+fn column_values(path: &str, columns: &[Column], cache: &MetadataCache) -> Vec<String> {
+    let Ok(repo) = Repository::open(path) else {
+        return vec![String::new(); columns.len()];
+    };
+    // Runs while skim owns the terminal; config errors are shown in the preview instead
+    let config = load_layered_config(Path::new(path)).config;
+    let base_branch = config.base_branch_for(&repo, &head_branch_name(&repo));
+    let metadata = columns
+        .iter()
+        .any(|&c| c != Column::Upstream)
+        .then(|| cache.get(path, &base_branch))
+        .flatten();
+
+    columns
+        .iter()
+        .map(|&column| match column {
+            Column::Upstream => format_ahead_behind(upstream_ahead_behind(&repo)),
+            Column::Base => metadata
+                .as_ref()
+                .map(|m| format_ahead_behind(m.base.as_ref().map(|b| (b.ahead, b.behind))))
+                .unwrap_or_default(),
+            Column::Dirty => match &metadata {
+                Some(m) if m.dirty > 0 => "*".to_string(),
+                _ => String::new(),
+            },
+        })
+        .collect()
+}
+
+/// Reasons to look at a worktree before removing or forgetting about it
+fn row_warnings(entry: &WorktreeEntry, stashes: &[Stash]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(operation) = entry.operation {
+        warnings.push(format!("{operation} in progress"));
+    }
+    match stashes_for_branch(stashes, &entry.branch).len() {
+        0 => {}
+        1 => warnings.push("1 stash".to_string()),
+        n => warnings.push(format!("{n} stashes")),
+    }
+    warnings
+}
+
+/// Move a match position in `text()` to the same character in the displayed row,
+/// which has the optional columns inserted at `at`
+fn shift_match(index: usize, at: usize, by: usize) -> usize {
+    if index >= at { index + by } else { index }
+}
+
+/// `shift_match` for a half-open range
+fn shift_range(start: usize, end: usize, at: usize, by: usize) -> (usize, usize) {
+    let end = if end > at { end + by } else { end };
+    (shift_match(start, at, by).min(end), end)
+}
+
+/// Picker header with the column titles
+fn header(columns: &[Column], theme: &Theme) -> String {
+    let extra: String = columns
+        .iter()
+        .map(|&c| format!("{:<width$} ", column_label(c), width = column_width(c)))
+        .collect();
+    format!(
+        "{}Git Worktree Manager | Tips: ^prefix for start match, 'exact for exact match\n\
+         ──────────────────────────────────────────────────────────────────────────\n\
+         {:<10} {:<40} {:<8} {extra}Directory",
+        theme.icon("🌲"),
+        "Updated",
+        "Branch",
+        "State"
+    )
+}
+
+impl SkimItem for WorktreeItem {
+    fn text(&self) -> Cow<'_, str> {
+        Cow::Owned(self.display_text.read().unwrap().clone())
+    }
+
+    fn display(&self, context: DisplayContext) -> Line<'_> {
+        if self.columns.is_empty() {
+            return context.to_line(self.text());
+        }
+
+        let extra = self.extra_columns();
+        let text = self.display_text.read().unwrap();
+        let (head, tail) = text.split_at(self.dirname_start);
+        let row = format!("{head}{extra}{tail}");
+
+        // Matches refer to `text()`; shift those in the directory column past the extra columns
+        let (byte_at, byte_by) = (head.len(), extra.len());
+        let (char_at, char_by) = (head.chars().count(), extra.chars().count());
+        let matches = match context.matches {
+            Matches::CharIndices(indices) => Matches::CharIndices(
+                indices
+                    .into_iter()
+                    .map(|i| shift_match(i, char_at, char_by))
+                    .collect(),
+            ),
+            Matches::CharRange(start, end) => {
+                let (start, end) = shift_range(start, end, char_at, char_by);
+                Matches::CharRange(start, end)
+            }
+            Matches::ByteRange(start, end) => {
+                let (start, end) = shift_range(start, end, byte_at, byte_by);
+                Matches::ByteRange(start, end)
+            }
+            Matches::None => Matches::None,
+        };
+        DisplayContext { matches, ..context }.to_line(Cow::Owned(row))
+    }
+
+    fn get_matching_ranges(&self) -> Option<&[(usize, usize)]> {
+        Some(&self.matching_ranges)
+    }
+
+    fn preview(&self, context: PreviewContext) -> ItemPreview {
+        let mode = self.preview_mode.get();
+        let theme = &self.theme;
+        let preview_result = match mode {
+            PreviewMode::Summary => {
+                generate_preview(&self.branch, &self.path, &self.state(), theme)
+            }
+            _ => generate_diff_preview(&self.branch, &self.path, mode, context.width, theme),
+        };
+        let preview = preview_result.unwrap_or_else(|e| format!("Error generating preview: {e}"));
+        ItemPreview::AnsiText(format!(
+            "{}View: {mode} ({PREVIEW_MODE_KEY}: {}, {PREVIEW_ROTATE_KEY}: move, \
+             {PREVIEW_TOGGLE_KEY}: hide)\n\n{preview}",
+            theme.icon("👁 "),
+            mode.next()
+        ))
+    }
+}
+
+/// Outcome of an interactive selection
+pub enum Selection {
+    /// The user accepted one or more items
+    Selected(Vec<WorktreeEntry>),
+    /// The user aborted the picker (Esc / Ctrl-C)
+    Aborted,
+}
+
+/// How the picker should behave
+#[derive(Debug, Clone, Default)]
+pub struct PickerOptions<'a> {
+    pub prompt: &'a str,
+    /// Shown before `prompt` unless emoji are turned off
+    pub prompt_icon: &'a str,
+    /// Show the preview panel
+    pub preview: bool,
+    /// Plain text instead of emoji, whatever the theme says
+    pub no_emoji: bool,
+    /// Allow marking several items with Tab
+    pub multi: bool,
+    /// Hide the main and current worktrees (used by `remove`)
+    pub removable_only: bool,
+    /// Offer only these worktrees instead of every worktree of the repository
+    pub entries: Option<Vec<WorktreeEntry>>,
+    /// Row order (default: `sort` from wt.config.yml)
+    pub sort: Option<SortOrder>,
+    /// Preview window and height overrides
+    pub layout: LayoutArgs,
+}
+
+/// Picker size options of the interactive subcommands
+#[derive(ClapArgs, Debug, Clone, Default)]
+pub struct LayoutArgs {
+    /// Preview position and size in skim's format, e.g. `down:40%` or `right:50%:hidden`;
+    /// implies --preview (default: `preview_window` in wt.config.yml)
+    #[arg(long, value_name = "SPEC")]
+    pub preview_window: Option<String>,
+
+    /// Picker height in lines or percent of the terminal, e.g. `20` or `60%`
+    /// (default: `height` in wt.config.yml)
+    #[arg(long)]
+    pub height: Option<String>,
+}
+
+/// A picker row with the values it can be sorted by
+struct Row {
+    commit_time: Option<i64>,
+    created: Option<i64>,
+    frecency: f64,
+    dirname: String,
+    item: WorktreeItem,
+}
+
+/// Sort rows by `sort`, most recent commit first among equals.
+///
+/// Rows without a commit or creation time go last.
+fn sort_rows(rows: &mut [Row], sort: SortOrder) {
+    let newest_first =
+        |a: Option<i64>, b: Option<i64>| b.unwrap_or(i64::MIN).cmp(&a.unwrap_or(i64::MIN));
+    rows.sort_by(|a, b| {
+        let order = match sort {
+            SortOrder::Frecency => b.frecency.total_cmp(&a.frecency),
+            SortOrder::Commit => cmp::Ordering::Equal,
+            SortOrder::Name => a.item.branch.cmp(&b.item.branch),
+            SortOrder::Dirname => a.dirname.cmp(&b.dirname),
+            SortOrder::Created => newest_first(a.created, b.created),
+        };
+        order.then_with(|| newest_first(a.commit_time, b.commit_time))
+    });
+}
+
+/// When a worktree was created: the timestamp prefix of directories made by `wt add`,
+/// else when its admin dir was set up. Unknown for the main worktree.
+fn created_time(entry: &WorktreeEntry, dirname: &str) -> Option<i64> {
+    if entry.is_main {
+        return None;
+    }
+    dir_name_timestamp(dirname).or(entry.created)
+}
+
+/// Remember a picked worktree for the `frecency` sort
+pub fn record_selection(path: &str) -> Result<()> {
+    let main_repo = open_main_repo(&open_current_repo()?)?;
+    let mut history = History::load(&main_repo);
+    history.record(path, Local::now().timestamp());
+    let existing: Vec<String> = collect_worktrees()?
+        .into_iter()
+        .map(|entry| entry.path)
+        .collect();
+    history.retain(&existing);
+    history.save()
+}
+
+/// Run the skim picker over all worktrees and return the chosen ones.
+///
+/// With `multi`, Tab marks several items; otherwise only the current item is returned.
+pub fn select_worktree(opts: PickerOptions) -> Result<Selection> {
+    let main_repo = open_main_repo(&open_current_repo()?)?;
+
+    // Collect basic worktree info
+    let mut worktrees = match opts.entries {
+        Some(entries) => entries,
+        None => collect_worktrees_quick()?,
+    };
+    if opts.removable_only {
+        worktrees.retain(|entry| entry.is_removable());
+    }
+
+    let config = main_worktree_dir(&main_repo)
+        .map(|dir| load_config(&dir))
+        .unwrap_or_default();
+    let columns = config.columns;
+    let theme = Arc::new(Theme {
+        ascii: config.theme.ascii || opts.no_emoji,
+        ..config.theme
+    });
+    if theme.ascii {
+        // Also for the messages printed once the picker is closed
+        set_no_emoji();
+    }
+    let sort = opts.sort.unwrap_or(config.sort);
+    let history = History::load(&main_repo);
+    let now = Local::now().timestamp();
+    let preview_mode = Arc::new(PreviewModeCell::default());
+    let stashes = list_stashes(&main_repo);
+
+    // Rows and their order only need the shared git dir, so skim gets every item
+    // before any (possibly slow) worktree directory is read; the background pass then
+    // checks each directory for the `missing`/`prunable` badge
+    let mut rows: Vec<Row> = worktrees
+        .iter()
+        .map(|entry| {
+            let timestamp = head_commit_time(&main_repo, entry);
+            let dirname = get_dirname(&entry.path);
+            let mut item = WorktreeItem::new(
+                entry.branch.clone(),
+                entry.path.clone(),
+                dirname.clone(),
+                timestamp.map_or_else(|| "unknown".to_string(), relative_time),
+                entry.state.clone(),
+                &columns,
+            );
+            item.is_main = entry.is_main;
+            item.is_current = entry.is_current;
+            item.preview_mode = Arc::clone(&preview_mode);
+            item.theme = Arc::clone(&theme);
+            let warnings = row_warnings(entry, &stashes);
+            if !warnings.is_empty() {
+                item = item.with_warning(&warnings.join(", "));
+            }
+            Row {
+                commit_time: timestamp,
+                created: created_time(entry, &dirname),
+                frecency: history.score(&entry.path, now),
+                dirname,
+                item,
+            }
+        })
+        .collect();
+
+    sort_rows(&mut rows, sort);
+    let items: Vec<Arc<WorktreeItem>> = rows.into_iter().map(|row| Arc::new(row.item)).collect();
+
+    // Send everything at once; the channel closing tells skim the list is complete
+    let (tx_item, rx_item): (SkimItemSender, SkimItemReceiver) = unbounded();
+    let _ = tx_item.send(
+        items
+            .iter()
+            .map(|item| Arc::clone(item) as Arc<dyn SkimItem>)
+            .collect(),
+    );
+    drop(tx_item);
+
+    // An explicit --preview-window is used as given, even on a narrow terminal
+    let (spec, narrow_width) = match &opts.layout.preview_window {
+        Some(spec) => (spec.as_str(), 0),
+        None => (config.preview_window.as_str(), config.preview_narrow_width),
+    };
+    let show_preview = opts.preview || opts.layout.preview_window.is_some();
+    let terminal_width = terminal::size().ok().map(|(width, _)| width);
+    let preview_window = preview_layout(spec, show_preview, terminal_width, narrow_width);
+    let height = opts.layout.height.clone().unwrap_or(config.height);
+
+    // Configure skim options using builder for better control
+    let mut options = SkimOptionsBuilder::default()
+        .height(height)
+        .multi(opts.multi)
+        .prompt(format!("{}{}", theme.icon(opts.prompt_icon), opts.prompt))
+        .preview("") // Required to enable SkimItem::preview() method
+        .preview_window(preview_window)
+        .header(header(&columns, &theme))
+        .ansi(true) // REQUIRED for colored highlights
+        .regex(false) // IMPORTANT: extended search with ' ^ ! etc.
+        .exact(false) // Start fuzzy; ' toggles exact
+        .algorithm(FuzzyAlgorithm::SkimV2) // Be explicit about algorithm
+        // Color scheme for highlights
+        .color(theme.picker.clone())
+        .build()?;
+
+    // Bound after `build()`, which replaces the key map with the parsed `bind` options
+    let cycle_mode = ActionCallback::new_sync(move |_| {
+        preview_mode.cycle();
+        Ok(vec![Event::Action(Action::RefreshPreview)])
+    });
+    bind(&mut options, PREVIEW_MODE_KEY, Action::Custom(cycle_mode))?;
+
+    bind(&mut options, PREVIEW_TOGGLE_KEY, Action::TogglePreview)?;
+    let rotate_preview = ActionCallback::new_sync(|app: &mut App| {
+        let window = &mut app.options.preview_window;
+        window.direction = next_direction(window.direction);
+        window.hidden = false;
+        app.layout_template = LayoutTemplate::from_options(&app.options, app.header.height());
+        app.needs_render.store(true, Ordering::Relaxed);
+        Ok(vec![Event::Action(Action::RefreshPreview)])
+    });
+    bind(
+        &mut options,
+        PREVIEW_ROTATE_KEY,
+        Action::Custom(rotate_preview),
+    )?;
+
+    // Run skim
+    let output = match run_skim(options, rx_item, items, MetadataCache::load(&main_repo)) {
+        Ok(output) if !output.is_abort => output,
+        _ => return Ok(Selection::Aborted),
+    };
+
+    if output.selected_items.is_empty() {
+        return Ok(Selection::Aborted);
+    }
+
+    // Map the chosen rows back to their worktree entries by path, with the state found
+    // by the background pass
+    let selected = output
+        .selected_items
+        .iter()
+        .map(|selected_item| {
+            let item = selected_item
+                .downcast_item::<WorktreeItem>()
+                .ok_or_else(|| anyhow::anyhow!("Could not get item details"))?;
+            let entry = worktrees
+                .iter()
+                .find(|entry| entry.path == item.path)
+                .ok_or_else(|| anyhow::anyhow!("Could not get item details"))?;
+            Ok(WorktreeEntry {
+                state: item.state(),
+                ..entry.clone()
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Selection::Selected(selected))
+}
+
+/// Bind `key` to `action` in built skim options
+fn bind(options: &mut SkimOptions, key: &str, action: Action) -> Result<()> {
+    let key = parse_key(key).map_err(|e| anyhow::anyhow!("{e}"))?;
+    options.keymap.insert(key, vec![action]);
+    Ok(())
+}
+
+/// Where `PREVIEW_ROTATE_KEY` moves the preview next: clockwise, starting on the right
+fn next_direction(direction: Direction) -> Direction {
+    match direction {
+        Direction::Right => Direction::Down,
+        Direction::Down => Direction::Left,
+        Direction::Left => Direction::Up,
+        Direction::Up => Direction::Right,
+    }
+}
+
+/// Preview window for `spec`. A preview beside the list moves below it when the terminal
+/// is narrower than `narrow_width` columns, where the list would be too cramped.
+fn preview_layout(
+    spec: &str,
+    show: bool,
+    terminal_width: Option<u16>,
+    narrow_width: u16,
+) -> PreviewLayout {
+    let mut layout = PreviewLayout::from(spec);
+    if !show {
+        layout.hidden = true;
+    }
+    let beside = matches!(layout.direction, Direction::Left | Direction::Right);
+    if beside && terminal_width.is_some_and(|width| width < narrow_width) {
+        layout.direction = Direction::Down;
+        layout.size = Size::Percent(NARROW_PREVIEW_PERCENT);
+    }
+    layout
+}
+
+/// Same as `Skim::run_with`, plus a background pass that checks each worktree directory,
+/// fills in the optional columns and asks skim to redraw as each row is completed
+fn run_skim(
+    options: SkimOptions,
+    source: SkimItemReceiver,
+    items: Vec<Arc<WorktreeItem>>,
+    cache: MetadataCache,
+) -> Result<SkimOutput> {
+    let mut skim = Skim::init(options, Some(source)).map_err(|e| anyhow::anyhow!("{e}"))?;
+    skim.start();
+    skim.init_tui().map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    let needs_render = Arc::clone(&skim.app().needs_render);
+    thread::spawn(move || {
+        items.par_iter().for_each(|item| {
+            let state = item.state();
+            let checked = checkout_state(&WorktreeEntry {
+                branch: item.branch.clone(),
+                path: item.path.clone(),
+                state: state.clone(),
+                is_main: item.is_main,
+                is_current: item.is_current,
+                operation: None,
+                created: None,
+            });
+            if checked != state {
+                item.set_state(checked);
+                needs_render.store(true, Ordering::Relaxed);
+            }
+            if !item.columns.is_empty() {
+                item.set_column_values(column_values(&item.path, &item.columns, &cache));
+                needs_render.store(true, Ordering::Relaxed);
+            }
+        });
+        let _ = cache.save(None);
+    });
+
+    tokio::runtime::Runtime::new()?
+        .block_on(async {
+            skim.enter().await?;
+            skim.run().await
+        })
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    Ok(skim.output())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worktree::Operation;
+
+    #[test]
+    fn test_worktree_item_text_format() {
+        let item = WorktreeItem::new(
+            "feature-branch".to_string(),
+            "/path/to/worktree".to_string(),
+            "worktree".to_string(),
+            "2h ago".to_string(),
+            WorktreeState::Branch,
+            &[],
+        );
+
+        // text() now returns formatted display string
+        let text = item.text();
+        // Format: "{:<10} {:<40} {}"
+        assert!(text.contains("2h ago"));
+        assert!(text.contains("feature-branch"));
+        assert!(text.contains("worktree"));
+    }
+
+    #[test]
+    fn test_search_format_vs_display_format() {
+        let item = WorktreeItem::new(
+            "feature-branch".to_string(),
+            "/path/to/worktree".to_string(),
+            "worktree".to_string(),
+            "2h ago".to_string(),
+            WorktreeState::Branch,
+            &[],
+        );
+
+        // text() and display() now use the same format
+        let text = item.text();
+
+        // Should be formatted with fixed widths
+        assert!(text.starts_with("2h ago"));
+        assert!(text.contains("feature-branch"));
+        assert!(text.contains("worktree"));
+    }
+
+    #[test]
+    fn test_display_alignment() {
+        // Test various branch name lengths
+        let test_cases = vec![
+            ("main", "2h ago", "project"),
+            (
+                "feature-very-long-branch-name-that-exceeds-40-chars",
+                "10d ago",
+                "my-workspace",
+            ),
+            ("fix", "now", "dir"),
+        ];
+
+        for (branch, updated, dirname) in test_cases {
+            let item = WorktreeItem::new(
+                branch.to_string(),
+                format!("/path/to/{dirname}"),
+                dirname.to_string(),
+                updated.to_string(),
+                WorktreeState::Branch,
+                &[],
+            );
+
+            // Verify the text format has fixed-width columns
+            let search_text = item.text();
+            // Format is now: updated (10 chars) | branch (40 chars) | state (8 chars) | dirname
+            assert!(search_text.starts_with(updated));
+            assert!(search_text.contains(&item.branch));
+            assert!(search_text.contains(dirname));
+        }
+    }
+
+    #[test]
+    fn test_state_badge_column_is_searchable() {
+        let item = WorktreeItem::new(
+            "detached@abc1234".to_string(),
+            "/path/to/wt".to_string(),
+            "wt".to_string(),
+            "1d ago".to_string(),
+            WorktreeState::Detached("abc1234".to_string()),
+            &[],
+        );
+
+        let text = item.text();
+        let (start, end) = item.get_matching_ranges().unwrap()[2];
+        assert_eq!(&text[start..end], "detached");
+        assert!(text.ends_with(" wt"));
+    }
+
+    #[test]
+    fn test_set_state_rewrites_only_the_badge() {
+        let item = WorktreeItem::new(
+            "topic".to_string(),
+            "/path/to/wt".to_string(),
+            "wt".to_string(),
+            "1d ago".to_string(),
+            WorktreeState::Branch,
+            &[],
+        )
+        .with_warning("2 stashes");
+        let before = item.text().to_string();
+
+        item.set_state(WorktreeState::Prunable);
+        assert_eq!(item.state(), WorktreeState::Prunable);
+        let text = item.text();
+        assert_eq!(text.len(), before.len());
+        let (start, end) = item.get_matching_ranges().unwrap()[2];
+        assert_eq!(&text[start..end], "prunable");
+        assert_eq!(text[..start], before[..start]);
+        assert_eq!(text[end..], before[end..]);
+    }
+
+    fn row(branch: &str, commit_time: i64, created: Option<i64>, frecency: f64) -> Row {
+        Row {
+            commit_time: Some(commit_time),
+            created,
+            frecency,
+            dirname: format!("dir-{branch}"),
+            item: WorktreeItem::new(
+                branch.to_string(),
+                format!("/path/to/{branch}"),
+                format!("dir-{branch}"),
+                "now".to_string(),
+                WorktreeState::Branch,
+                &[],
+            ),
+        }
+    }
+
+    fn sorted_branches(sort: SortOrder) -> Vec<String> {
+        let mut rows = vec![
+            row("b", 300, Some(10), 0.0),
+            row("c", 100, None, 4.0),
+            row("a", 200, Some(20), 0.0),
+        ];
+        sort_rows(&mut rows, sort);
+        rows.into_iter().map(|row| row.item.branch).collect()
+    }
+
+    #[test]
+    fn test_sort_rows() {
+        assert_eq!(sorted_branches(SortOrder::Commit), ["b", "a", "c"]);
+        // Never-picked rows fall back to commit order
+        assert_eq!(sorted_branches(SortOrder::Frecency), ["c", "b", "a"]);
+        assert_eq!(sorted_branches(SortOrder::Name), ["a", "b", "c"]);
+        assert_eq!(sorted_branches(SortOrder::Dirname), ["a", "b", "c"]);
+        assert_eq!(sorted_branches(SortOrder::Created), ["a", "b", "c"]);
+    }
+
+    fn rendered(item: &WorktreeItem, matches: Matches) -> Vec<String> {
+        let context = DisplayContext {
+            matches,
+            ..Default::default()
+        };
+        item.display(context)
+            .spans
+            .iter()
+            .map(|span| span.content.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_columns_fill_in_without_changing_search_text() {
+        let item = WorktreeItem::new(
+            "topic".to_string(),
+            "/path/to/wt".to_string(),
+            "wt".to_string(),
+            "1d ago".to_string(),
+            WorktreeState::Branch,
+            &[Column::Upstream, Column::Dirty],
+        );
+        let text_before = item.text().to_string();
+        assert!(rendered(&item, Matches::None).concat().contains("…"));
+
+        item.set_column_values(vec![format_ahead_behind(Some((2, 1))), "*".to_string()]);
+        assert_eq!(item.text(), text_before);
+        let row = rendered(&item, Matches::None).concat();
+        assert!(row.contains("↑2↓1"));
+        assert!(row.ends_with("*     wt"));
+
+        // A match on the directory still highlights the directory
+        let (start, end) = item.get_matching_ranges().unwrap()[3];
+        assert_eq!(&item.text()[start..end], "wt");
+        let spans = rendered(&item, Matches::ByteRange(start, end));
+        assert_eq!(spans[1], "wt");
+    }
+
+    #[test]
+    fn test_header_matches_row_layout() {
+        let header = header(&[Column::Base, Column::Dirty], &Theme::default());
+        let titles = header.lines().last().unwrap();
+        let row = WorktreeItem::new(
+            "topic".to_string(),
+            "/path/to/wt".to_string(),
+            "wt".to_string(),
+            "1d ago".to_string(),
+            WorktreeState::Branch,
+            &[Column::Base, Column::Dirty],
+        );
+        row.set_column_values(vec!["=".to_string(), String::new()]);
+        let row = rendered(&row, Matches::None).concat();
+        assert_eq!(titles.find("Directory"), row.find(" wt").map(|i| i + 1));
+    }
+
+    #[test]
+    fn test_warning_is_shown_but_not_searched() {
+        let entry = WorktreeEntry {
+            branch: "topic".to_string(),
+            path: "/path/to/wt".to_string(),
+            state: WorktreeState::Branch,
+            is_main: false,
+            is_current: false,
+            operation: Some(Operation::Rebase),
+            created: None,
+        };
+        let stash = |branch: &str| Stash {
+            index: 0,
+            branch: Some(branch.to_string()),
+            message: "wip".to_string(),
+            time: None,
+        };
+        let warnings = row_warnings(&entry, &[stash("topic"), stash("main"), stash("topic")]);
+        assert_eq!(warnings, ["rebase in progress", "2 stashes"]);
+
+        let item = WorktreeItem::new(
+            "topic".to_string(),
+            "/path/to/wt".to_string(),
+            "wt".to_string(),
+            "1d ago".to_string(),
+            WorktreeState::Branch,
+            &[],
+        )
+        .with_warning(&warnings.join(", "));
+        assert!(item.text().ends_with(" wt ⚠ rebase in progress, 2 stashes"));
+        let (start, end) = item.get_matching_ranges().unwrap()[3];
+        assert_eq!(&item.text()[start..end], "wt");
+    }
+
+    #[test]
+    fn test_format_ahead_behind() {
+        assert_eq!(format_ahead_behind(None), "-");
+        assert_eq!(format_ahead_behind(Some((0, 0))), "=");
+        assert_eq!(format_ahead_behind(Some((3, 0))), "↑3");
+        assert_eq!(format_ahead_behind(Some((0, 4))), "↓4");
+    }
+
+    #[test]
+    fn test_preview_layout() {
+        let wide = preview_layout("right:60%:wrap", true, Some(200), 120);
+        assert_eq!(wide.direction, Direction::Right);
+        assert!(!wide.hidden);
+
+        let narrow = preview_layout("right:60%:wrap", true, Some(100), 120);
+        assert_eq!(narrow.direction, Direction::Down);
+        assert!(narrow.size == Size::Percent(NARROW_PREVIEW_PERCENT));
+        assert!(narrow.wrap);
+
+        assert_eq!(
+            preview_layout("up:30%", true, Some(100), 120).direction,
+            Direction::Up
+        );
+        assert_eq!(
+            preview_layout("left:50%", true, Some(100), 0).direction,
+            Direction::Left
+        );
+        assert_eq!(
+            preview_layout("left:50%", true, None, 120).direction,
+            Direction::Left
+        );
+        assert!(preview_layout("right:60%", false, Some(200), 120).hidden);
+    }
+
+    #[test]
+    fn test_next_direction_visits_every_side() {
+        let mut direction = Direction::Right;
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            direction = next_direction(direction);
+            seen.push(direction);
+        }
+        assert_eq!(
+            seen,
+            [
+                Direction::Down,
+                Direction::Left,
+                Direction::Up,
+                Direction::Right
+            ]
+        );
+    }
+}
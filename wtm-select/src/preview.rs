@@ -0,0 +1,529 @@
+use crate::cache::{
+    FileChange, MAX_BASE_FILES, MAX_CHANGED_FILES, MetadataCache, StatusCounts, StatusEntry,
+    compute_metadata,
+};
+use crate::config::load_layered_config;
+use crate::stash::{list_stashes, stashes_for_branch};
+use crate::theme::Theme;
+use crate::worktree::{Operation, WorktreeState, find_fork_point, relative_time};
+use anyhow::Result;
+use git2::{
+    Diff, DiffFormat, DiffOptions, DiffStatsFormat, Repository, SubmoduleIgnore, SubmoduleStatus,
+};
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Key that cycles the picker's preview through the `PreviewMode`s
+pub const PREVIEW_MODE_KEY: &str = "ctrl-v";
+
+/// Lines of a unified diff shown before it is cut off
+const MAX_DIFF_LINES: usize = 2000;
+
+/// Stat graph width when skim does not report the preview width
+const DEFAULT_STAT_WIDTH: usize = 80;
+
+/// What the preview pane shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PreviewMode {
+    /// Last commit, changed files, recent commits and the files changed vs the base
+    #[default]
+    Summary,
+    /// `git diff --stat` of the uncommitted changes and of the branch
+    Stat,
+    /// Colored unified diff of the uncommitted changes and of the branch
+    Diff,
+}
+
+impl PreviewMode {
+    const ALL: [PreviewMode; 3] = [PreviewMode::Summary, PreviewMode::Stat, PreviewMode::Diff];
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for PreviewMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PreviewMode::Summary => "summary",
+            PreviewMode::Stat => "stat",
+            PreviewMode::Diff => "diff",
+        })
+    }
+}
+
+/// Preview mode shared by the picker's key binding and its items
+#[derive(Debug, Default)]
+pub struct PreviewModeCell(AtomicU8);
+
+impl PreviewModeCell {
+    pub fn get(&self) -> PreviewMode {
+        PreviewMode::ALL[usize::from(self.0.load(Ordering::Relaxed))]
+    }
+
+    /// Switch to the next mode
+    pub fn cycle(&self) {
+        self.0.store(self.get().next() as u8, Ordering::Relaxed);
+    }
+}
+
+pub fn generate_preview(
+    branch: &str,
+    path: &str,
+    state: &WorktreeState,
+    theme: &Theme,
+) -> Result<String> {
+    let mut output = String::new();
+    let warning = theme.symbol("⚠️ ", "!");
+
+    // The picker owns the terminal, so config problems are shown here instead of on stderr
+    let layered = load_layered_config(Path::new(path));
+    for error in &layered.errors {
+        output.push_str(&format!(
+            "{warning} {}\n",
+            theme
+                .warning
+                .paint(format!("Invalid config ignored: {error}"))
+        ));
+    }
+    if !layered.errors.is_empty() {
+        output.push('\n');
+    }
+
+    // Header info
+    output.push_str(&format!("{}Branch: {branch}\n\n", theme.icon("🌳")));
+    output.push_str(&format!("{}Path: {path}\n\n", theme.icon("📁")));
+    if *state != WorktreeState::Branch {
+        output.push_str(&format!("{}State: {state}\n\n", theme.icon("🏷 ")));
+    }
+
+    // Open repository
+    if let Ok(repo) = Repository::open(path) {
+        let base_branch = &layered.config.diff_target(&repo, branch);
+
+        // Diff stats are the slow part and reused while HEAD and the base are unchanged;
+        // the status is rescanned, as edits made without git leave the cache key alone
+        let cache = MetadataCache::load(&repo);
+        let metadata = cache
+            .get_fresh(path, base_branch)
+            .unwrap_or_else(|| compute_metadata(&repo, base_branch));
+        let _ = cache.save(None);
+
+        if let Some(operation) = Operation::of(&repo) {
+            output.push_str(&format!(
+                "{warning} {}\n\n",
+                theme
+                    .warning
+                    .paint(format!("{operation} in progress ({})", operation.hint()))
+            ));
+        }
+
+        // Get last commit info
+        if let Some(timestamp) = metadata.last_commit {
+            let relative = theme.time.paint(relative_time(timestamp));
+            let summary = metadata.summary.as_deref().unwrap_or("No message");
+            output.push_str(&format!(
+                "{}Last commit: {relative}: {summary}\n\n",
+                theme.icon("🕐")
+            ));
+        }
+
+        // Get status
+        let title = format!("Changed files{}:", format_status_counts(&metadata.counts));
+        output.push_str(&theme.section("📝", &title));
+
+        if metadata.dirty == 0 {
+            output.push_str(&format!("  {}Working tree clean\n", theme.icon("✨")));
+        } else {
+            for change in &metadata.changed_files {
+                output.push_str(&format!("  {}\n", paint_status(theme, change)));
+            }
+
+            if metadata.dirty > MAX_CHANGED_FILES {
+                let more = metadata.dirty - MAX_CHANGED_FILES;
+                output.push_str(&format!("  ... and {more} more\n"));
+            }
+        }
+        output.push('\n');
+
+        // Stashes are shared by all worktrees; show the ones made on this branch
+        let stashes = list_stashes(&repo);
+        let stashes = stashes_for_branch(&stashes, branch);
+        if !stashes.is_empty() {
+            let title = format!("Stashes ({}):", stashes.len());
+            output.push_str(&theme.section("📦", &title));
+            for stash in stashes {
+                let age = theme
+                    .time
+                    .paint(stash.time.map(relative_time).unwrap_or_default());
+                output.push_str(&format!(
+                    "  stash@{{{}}} {age}: {}\n",
+                    stash.index, stash.message
+                ));
+            }
+            output.push('\n');
+        }
+
+        let submodules = submodule_states(&repo);
+        if !submodules.is_empty() {
+            output.push_str(&theme.section("🧩", "Submodules:"));
+            for (name, state) in submodules {
+                output.push_str(&format!("  {name}: {state}\n"));
+            }
+            output.push('\n');
+        }
+
+        // Get recent commits
+        output.push_str(&theme.section("📜", "Recent commits:"));
+
+        if let Ok(mut revwalk) = repo.revwalk() {
+            let _ = revwalk.push_head();
+
+            for oid in revwalk.take(10).flatten() {
+                if let Ok(commit) = repo.find_commit(oid) {
+                    let id_str = theme.hash.paint(&oid.to_string()[..7]);
+                    let summary = commit.summary().unwrap_or("No message");
+                    output.push_str(&format!("  {id_str} {summary}\n"));
+                }
+            }
+        }
+        output.push('\n');
+
+        // Get diff stat against base branch
+        let title = match &metadata.base {
+            Some(base) if base.fork_point.len() >= 7 => {
+                format!("Diff vs {base_branch} (since {}):", &base.fork_point[..7])
+            }
+            _ => format!("Diff vs {base_branch}:"),
+        };
+        output.push_str(&theme.section("📊", &title));
+
+        if let Some(base) = &metadata.base {
+            output.push_str(&format!(
+                "  {} file(s) changed, {} {}\n",
+                base.files_changed,
+                theme.added.paint(format!("+{}", base.insertions)),
+                theme.deleted.paint(format!("-{}", base.deletions))
+            ));
+
+            // Show changed files (max 15)
+            for change in &base.files {
+                output.push_str(&format!("  {}\n", paint_file_change(theme, change)));
+            }
+
+            if base.files_changed > MAX_BASE_FILES {
+                output.push_str(&format!(
+                    "  ... and {} more files\n",
+                    base.files_changed - base.files.len()
+                ));
+            }
+
+            if base.files.is_empty() {
+                output.push_str(&format!(
+                    "  {}No changes from {base_branch}\n",
+                    theme.icon("✨")
+                ));
+            }
+        } else if repo.head().is_ok() {
+            output.push_str(&format!("  ({base_branch} not found)\n"));
+        }
+    } else {
+        output.push_str("Error: Cannot access worktree\n");
+    }
+
+    Ok(output)
+}
+
+/// A `git status --short` line, colored like git does: index letter, working tree letter
+fn paint_status(theme: &Theme, entry: &StatusEntry) -> String {
+    let code = if entry.is_conflicted() {
+        theme.conflicted.paint(&entry.code)
+    } else if entry.code == "??" {
+        theme.untracked.paint(&entry.code)
+    } else {
+        let (index, worktree) = entry.code.split_at(1);
+        format!(
+            "{}{}",
+            theme.staged.paint(index),
+            theme.unstaged.paint(worktree)
+        )
+    };
+    match &entry.renamed_from {
+        Some(from) => format!("{code} {from} -> {}", entry.path),
+        None => format!("{code} {}", entry.path),
+    }
+}
+
+/// A file changed vs the base branch, with its status letter colored
+fn paint_file_change(theme: &Theme, change: &FileChange) -> String {
+    let style = match change.status {
+        'A' => &theme.added,
+        'D' => &theme.deleted,
+        _ => &theme.modified,
+    };
+    format!("{} {}", style.paint(change.status), change.path)
+}
+
+/// Submodules of `repo` and what is going on in them, e.g. `new commits, untracked content`
+fn submodule_states(repo: &Repository) -> Vec<(String, String)> {
+    let Ok(submodules) = repo.submodules() else {
+        return Vec::new();
+    };
+    submodules
+        .iter()
+        .filter_map(|submodule| {
+            let name = submodule.name()?;
+            let status = repo.submodule_status(name, SubmoduleIgnore::None).ok()?;
+            let mut states = Vec::new();
+            if status.contains(SubmoduleStatus::WD_UNINITIALIZED) {
+                states.push("not initialized");
+            }
+            if status.contains(SubmoduleStatus::WD_MODIFIED) {
+                states.push("new commits");
+            }
+            if status
+                .intersects(SubmoduleStatus::WD_INDEX_MODIFIED | SubmoduleStatus::WD_WD_MODIFIED)
+            {
+                states.push("modified content");
+            }
+            if status.contains(SubmoduleStatus::WD_UNTRACKED) {
+                states.push("untracked content");
+            }
+            let state = if states.is_empty() {
+                "clean".to_string()
+            } else {
+                states.join(", ")
+            };
+            Some((name.to_string(), state))
+        })
+        .collect()
+}
+
+/// Non-zero counts in parentheses, e.g. ` (1 conflicted, 2 staged, 3 untracked)`
+fn format_status_counts(counts: &StatusCounts) -> String {
+    let parts: Vec<String> = [
+        (counts.conflicted, "conflicted"),
+        (counts.staged, "staged"),
+        (counts.unstaged, "unstaged"),
+        (counts.untracked, "untracked"),
+        (counts.ignored, "ignored"),
+    ]
+    .into_iter()
+    .filter(|&(count, _)| count > 0)
+    .map(|(count, kind)| format!("{count} {kind}"))
+    .collect();
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", parts.join(", "))
+    }
+}
+
+/// Preview of the uncommitted changes (`git diff HEAD`) and of the branch
+/// (`git diff <base>...HEAD`), as stats or as a colored unified diff
+pub fn generate_diff_preview(
+    branch: &str,
+    path: &str,
+    mode: PreviewMode,
+    width: usize,
+    theme: &Theme,
+) -> Result<String> {
+    let mut output = format!("{}Branch: {branch}\n\n", theme.icon("🌳"));
+
+    let Ok(repo) = Repository::open(path) else {
+        output.push_str("Error: Cannot access worktree\n");
+        return Ok(output);
+    };
+    let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    let render = |diff: &Diff, clean: &str, output: &mut String| -> Result<()> {
+        if diff.deltas().len() == 0 {
+            output.push_str(&format!("  {}{clean}\n", theme.icon("✨")));
+            return Ok(());
+        }
+        match mode {
+            PreviewMode::Diff => render_patch(diff, theme, output),
+            _ => render_stats(diff, width, theme, output)?,
+        }
+        Ok(())
+    };
+
+    output.push_str(&theme.section("📝", "Uncommitted changes:"));
+    // Untracked files too, as in the summary's counts
+    let mut opts = DiffOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    let uncommitted = repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut opts))?;
+    render(&uncommitted, "Working tree clean", &mut output)?;
+    output.push('\n');
+
+    let layered = load_layered_config(Path::new(path));
+    let base_branch = layered.config.diff_target(&repo, branch);
+    let Some(fork_point) = find_fork_point(&repo, &base_branch) else {
+        output.push_str(&theme.section("📊", &format!("Diff vs {base_branch}:")));
+        output.push_str(&format!("  ({base_branch} not found)\n"));
+        return Ok(output);
+    };
+    let short_id = &fork_point.id().to_string()[..7];
+    let title = format!("Diff vs {base_branch} (since {short_id}):");
+    output.push_str(&theme.section("📊", &title));
+    let committed = repo.diff_tree_to_tree(Some(&fork_point.tree()?), head_tree.as_ref(), None)?;
+    render(
+        &committed,
+        &format!("No changes from {base_branch}"),
+        &mut output,
+    )?;
+
+    Ok(output)
+}
+
+/// Append `git diff --stat` of `diff`, with the `+`/`-` graph colored
+fn render_stats(diff: &Diff, width: usize, theme: &Theme, output: &mut String) -> Result<()> {
+    let width = if width == 0 {
+        DEFAULT_STAT_WIDTH
+    } else {
+        width
+    };
+    let stats = diff.stats()?.to_buf(DiffStatsFormat::FULL, width)?;
+    for line in stats.as_str().unwrap_or_default().lines() {
+        output.push_str(&paint_stat_line(line, theme));
+        output.push('\n');
+    }
+    Ok(())
+}
+
+/// Color the `+`/`-` graph of a file's stat line. Binary files (`| Bin 12 -> 34 bytes`)
+/// and the summary line have no graph and are left alone.
+fn paint_stat_line(line: &str, theme: &Theme) -> String {
+    let graph = line
+        .rsplit_once('|')
+        .and_then(|(_, changes)| changes.rsplit_once(' '))
+        .map(|(_, graph)| graph)
+        .filter(|graph| !graph.is_empty() && graph.chars().all(|c| c == '+' || c == '-'));
+    let Some(graph) = graph else {
+        return line.to_string();
+    };
+    let painted = graph
+        .replace('+', &theme.added.paint('+'))
+        .replace('-', &theme.deleted.paint('-'));
+    format!("{}{painted}", &line[..line.len() - graph.len()])
+}
+
+/// Append `diff` as a colored unified diff, cut off after `MAX_DIFF_LINES`
+fn render_patch(diff: &Diff, theme: &Theme, output: &mut String) {
+    let mut lines = 0;
+    // Returning false from the callback stops the walk, which git2 reports as an error
+    let _ = diff.print(DiffFormat::Patch, |_, _, line| {
+        if lines >= MAX_DIFF_LINES {
+            return false;
+        }
+        let content = String::from_utf8_lossy(line.content());
+        let (style, prefix) = match line.origin() {
+            '+' => (Some(&theme.added), "+"),
+            '-' => (Some(&theme.deleted), "-"),
+            ' ' => (None, " "),
+            'F' => (Some(&theme.heading), ""),
+            'H' => (Some(&theme.hunk), ""),
+            // "\ No newline at end of file", after a line that was printed with one
+            _ => (None, ""),
+        };
+        let content = content.trim_start_matches('\n');
+        for text in content.lines() {
+            lines += 1;
+            let text = format!("{prefix}{text}");
+            match style {
+                Some(style) => output.push_str(&format!("{}\n", style.paint(text))),
+                None => output.push_str(&format!("{text}\n")),
+            }
+        }
+        true
+    });
+    if lines >= MAX_DIFF_LINES {
+        output.push_str(&format!(
+            "  ... diff cut off after {MAX_DIFF_LINES} lines\n"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add::tests::init_repo;
+    use git2::Signature;
+    use std::fs;
+
+    #[test]
+    fn test_only_the_stat_graph_is_colored() {
+        let theme = Theme::default();
+        assert_eq!(
+            paint_stat_line(" src/a-b.rs | 3 ++-", &theme),
+            format!(
+                " src/a-b.rs | 3 {}{}{}",
+                theme.added.paint('+'),
+                theme.added.paint('+'),
+                theme.deleted.paint('-')
+            )
+        );
+        let binary = " logo.png   | Bin 123 -> 456 bytes";
+        assert_eq!(paint_stat_line(binary, &theme), binary);
+        let summary = " 2 files changed, 2 insertions(+), 1 deletion(-)";
+        assert_eq!(paint_stat_line(summary, &theme), summary);
+    }
+
+    #[test]
+    fn test_preview_mode_cycles_back_to_summary() {
+        let cell = PreviewModeCell::default();
+        assert_eq!(cell.get(), PreviewMode::Summary);
+        cell.cycle();
+        assert_eq!(cell.get(), PreviewMode::Stat);
+        cell.cycle();
+        assert_eq!(cell.get(), PreviewMode::Diff);
+        cell.cycle();
+        assert_eq!(cell.get(), PreviewMode::Summary);
+    }
+
+    #[test]
+    fn test_diff_preview_shows_uncommitted_changes() {
+        let (dir, repo) = init_repo();
+        fs::write(dir.path().join("notes.txt"), "first\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("notes.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("test", "test@example.com").unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "notes", &tree, &[&parent])
+            .unwrap();
+        fs::write(dir.path().join("notes.txt"), "changed\n").unwrap();
+        fs::create_dir(dir.path().join("new")).unwrap();
+        fs::write(dir.path().join("new/todo.txt"), "untracked\n").unwrap();
+        let file = "notes.txt";
+        let path = dir.path().to_str().unwrap();
+
+        let theme = Theme::default();
+
+        let stat = generate_diff_preview("main", path, PreviewMode::Stat, 60, &theme).unwrap();
+        assert!(stat.contains(&format!(" {file} ")));
+        assert!(stat.contains(&theme.added.paint('+')));
+        assert!(stat.contains(" new/todo.txt "));
+        assert!(stat.contains("2 files changed"));
+
+        let diff = generate_diff_preview("main", path, PreviewMode::Diff, 60, &theme).unwrap();
+        let header = format!("diff --git a/{file} b/{file}");
+        assert!(diff.contains(&theme.heading.paint(header)));
+        assert!(diff.contains(&theme.deleted.paint("-first")));
+        assert!(diff.contains(&theme.added.paint("+changed")));
+        assert!(diff.contains(&theme.hunk.paint("@@ -1 +1 @@")));
+        assert!(diff.contains(&theme.added.paint("+untracked")));
+
+        let ascii = Theme {
+            ascii: true,
+            ..Theme::default()
+        };
+        let plain = generate_diff_preview("main", path, PreviewMode::Stat, 60, &ascii).unwrap();
+        assert!(plain.starts_with("Branch: main"));
+        let section = ascii.heading.paint("Uncommitted changes:");
+        assert!(plain.contains(&format!("\n{section}\n")));
+    }
+}
@@ -0,0 +1,445 @@
+use crate::config::load_config;
+use crate::picker::{LayoutArgs, PickerOptions, Selection, select_worktree};
+use crate::remove::remove_selected;
+use crate::worktree::{
+    WorktreeEntry, WorktreeState, collect_worktrees, find_base_commit, get_last_commit_info,
+    is_merged_into, main_worktree_dir, open_current_repo, open_main_repo, relative_time,
+};
+use anyhow::{Result, bail};
+use chrono::{Duration, Local};
+use clap::Args as ClapArgs;
+use git2::{BranchType, Oid, Repository};
+use rayon::prelude::*;
+use std::process::ExitCode;
+
+/// Arguments for `wtm-select prune`
+#[derive(ClapArgs, Debug)]
+pub struct PruneArgs {
+    /// Only print the table of worktrees that would be pruned
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Also prune worktrees whose last commit is older than this (e.g. 12h, 30d, 2w);
+    /// defaults to `prune_older_than` from wt.config.yml
+    #[arg(long, value_name = "AGE")]
+    pub older_than: Option<String>,
+
+    /// Remove every candidate without opening the picker or asking for confirmation
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+
+    /// Remove even if uncommitted changes, untracked files or unpushed commits would be lost
+    #[arg(short = 'f', long)]
+    pub force: bool,
+
+    /// Show preview panel in the interactive picker
+    #[arg(long)]
+    pub preview: bool,
+
+    /// Plain text instead of emoji in the picker (same as `theme.ascii: true`)
+    #[arg(long, visible_alias = "ascii")]
+    pub no_emoji: bool,
+
+    #[command(flatten)]
+    pub layout: LayoutArgs,
+}
+
+/// Why a worktree is a candidate for pruning
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PruneReason {
+    /// Every commit is already on the base branch
+    Merged(String),
+    /// The branch tracks an upstream that no longer exists
+    UpstreamGone(String),
+    /// The worktree directory was deleted
+    MissingDirectory,
+    /// The admin entry no longer points at a valid checkout
+    Prunable,
+    /// The last commit is older than the configured age
+    Stale(String),
+}
+
+impl std::fmt::Display for PruneReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Merged(base) => write!(f, "merged into {base}"),
+            Self::UpstreamGone(upstream) => write!(f, "upstream {upstream} gone"),
+            Self::MissingDirectory => write!(f, "directory missing"),
+            Self::Prunable => write!(f, "prunable"),
+            Self::Stale(age) => write!(f, "last commit {age}"),
+        }
+    }
+}
+
+/// A worktree `prune` would remove, and why
+#[derive(Debug, Clone)]
+pub struct PruneCandidate {
+    pub entry: WorktreeEntry,
+    pub reasons: Vec<PruneReason>,
+    /// Relative time of the last commit, as shown in the picker
+    pub updated: String,
+}
+
+/// Entry point for the `prune` subcommand
+pub fn run(args: PruneArgs) -> Result<ExitCode> {
+    let main_repo = open_main_repo(&open_current_repo()?)?;
+    let config = main_worktree_dir(&main_repo)
+        .map(|dir| load_config(&dir))
+        .unwrap_or_default();
+
+    let older_than = match args
+        .older_than
+        .as_deref()
+        .or(config.prune_older_than.as_deref())
+    {
+        Some(age) => Some(parse_age(age)?),
+        None => None,
+    };
+
+    let entries: Vec<WorktreeEntry> = collect_worktrees()?
+        .into_iter()
+        .filter(WorktreeEntry::is_removable)
+        .collect();
+    let main_path = main_repo.path().to_path_buf();
+    let candidates: Vec<PruneCandidate> = entries
+        .into_par_iter()
+        .filter_map(|entry| {
+            // Repository is not Sync; open one per rayon task
+            let repo = Repository::open(&main_path).ok()?;
+            let base_branch = config.base_branch_for(&repo, &entry.branch);
+            find_candidate(&repo, entry, &base_branch, older_than)
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        eprintln!("Nothing to prune");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    print!("{}", format_candidates(&candidates));
+    if args.dry_run {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let entries: Vec<WorktreeEntry> = candidates.into_iter().map(|c| c.entry).collect();
+    if args.yes {
+        return remove_selected(entries, true, args.force);
+    }
+
+    let opts = PickerOptions {
+        prompt: "Prune worktrees (Tab to mark) > ",
+        prompt_icon: "🧹",
+        preview: args.preview,
+        no_emoji: args.no_emoji,
+        layout: args.layout,
+        multi: true,
+        removable_only: true,
+        entries: Some(entries),
+        ..Default::default()
+    };
+    match select_worktree(opts)? {
+        Selection::Selected(selected) => remove_selected(selected, false, args.force),
+        Selection::Aborted => Ok(ExitCode::from(crate::EXIT_ABORTED)),
+    }
+}
+
+/// Check a worktree against every prune criterion; `None` if it should be kept
+pub fn find_candidate(
+    main_repo: &Repository,
+    entry: WorktreeEntry,
+    base_branch: &str,
+    older_than: Option<Duration>,
+) -> Option<PruneCandidate> {
+    let mut reasons = Vec::new();
+
+    match entry.state {
+        WorktreeState::Missing => reasons.push(PruneReason::MissingDirectory),
+        WorktreeState::Prunable => reasons.push(PruneReason::Prunable),
+        _ => {}
+    }
+
+    let head_oid = head_oid(main_repo, &entry);
+    if let Some(oid) = head_oid
+        && is_merged_into(main_repo, oid, base_branch) == Some(true)
+        && has_own_commits(main_repo, &entry.branch, oid, base_branch)
+    {
+        reasons.push(PruneReason::Merged(base_branch.to_string()));
+    }
+    if let Some(upstream) = gone_upstream(main_repo, &entry.branch) {
+        reasons.push(PruneReason::UpstreamGone(upstream));
+    }
+
+    // Same timestamp as the picker; fall back to the branch tip for missing directories
+    let timestamp = get_last_commit_info(&entry.path).0.or_else(|| {
+        let commit = main_repo.find_commit(head_oid?).ok()?;
+        Some(commit.time().seconds())
+    });
+    let updated = timestamp
+        .map(relative_time)
+        .unwrap_or_else(|| "unknown".to_string());
+    if let (Some(max_age), Some(ts)) = (older_than, timestamp)
+        && Local::now().timestamp() - ts > max_age.num_seconds()
+    {
+        reasons.push(PruneReason::Stale(updated.clone()));
+    }
+
+    (!reasons.is_empty()).then_some(PruneCandidate {
+        entry,
+        reasons,
+        updated,
+    })
+}
+
+/// Whether `branch` has had commits of its own, so that being contained in the base
+/// means it was merged. A branch fresh from `wt add` is contained too, but only because
+/// nothing was done on it yet: it still sits where it was created (a single reflog
+/// entry) or at the tip of the base branch.
+fn has_own_commits(repo: &Repository, branch: &str, head_oid: Oid, base_branch: &str) -> bool {
+    if find_base_commit(repo, base_branch).is_some_and(|base| base.id() == head_oid) {
+        return false;
+    }
+    match repo.reflog(&format!("refs/heads/{branch}")) {
+        Ok(reflog) if !reflog.is_empty() => reflog.len() > 1,
+        _ => true,
+    }
+}
+
+/// Commit checked out in a worktree, read from the branch when the directory is unusable
+fn head_oid(main_repo: &Repository, entry: &WorktreeEntry) -> Option<Oid> {
+    if let Ok(repo) = Repository::open(&entry.path)
+        && let Ok(head) = repo.head()
+    {
+        return head.target();
+    }
+    main_repo
+        .find_branch(&entry.branch, BranchType::Local)
+        .ok()?
+        .get()
+        .target()
+}
+
+/// Name of the configured upstream of `branch` if its remote-tracking ref no longer exists
+fn gone_upstream(repo: &Repository, branch: &str) -> Option<String> {
+    let upstream = repo
+        .branch_upstream_name(&format!("refs/heads/{branch}"))
+        .ok()?;
+    let upstream = upstream.as_str()?;
+    if repo.find_reference(upstream).is_ok() {
+        return None;
+    }
+    Some(
+        upstream
+            .strip_prefix("refs/remotes/")
+            .unwrap_or(upstream)
+            .to_string(),
+    )
+}
+
+/// Parse ages such as `90m`, `12h`, `30d` or `2w`; a bare number means days
+pub fn parse_age(age: &str) -> Result<Duration> {
+    let age = age.trim();
+    let (number, unit) = match age.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => age.split_at(idx),
+        None => (age, "d"),
+    };
+    let Ok(n) = number.parse::<i64>() else {
+        bail!("Invalid age: {age:?} (expected e.g. 12h, 30d, 2w)");
+    };
+    match unit {
+        "m" => Ok(Duration::minutes(n)),
+        "h" => Ok(Duration::hours(n)),
+        "d" => Ok(Duration::days(n)),
+        "w" => Ok(Duration::weeks(n)),
+        _ => bail!("Invalid age unit in {age:?} (use m, h, d or w)"),
+    }
+}
+
+/// Dry-run table: branch, last commit, reasons and path
+pub fn format_candidates(candidates: &[PruneCandidate]) -> String {
+    let width = candidates
+        .iter()
+        .map(|c| c.entry.branch.len())
+        .chain(["Branch".len()])
+        .max()
+        .unwrap_or(0);
+
+    let mut output = format!("{:<width$}  {:<10} Reasons\n", "Branch", "Updated");
+    for candidate in candidates {
+        let reasons = candidate
+            .reasons
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        output.push_str(&format!(
+            "{:<width$}  {:<10} {reasons}\n{:<width$}  {}\n",
+            candidate.entry.branch, candidate.updated, "", candidate.entry.path
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add::add_worktree;
+    use crate::add::tests::init_repo;
+    use std::fs;
+    use std::path::Path;
+
+    fn entry(branch: &str, path: &Path, state: WorktreeState) -> WorktreeEntry {
+        WorktreeEntry {
+            branch: branch.to_string(),
+            path: path.to_string_lossy().to_string(),
+            state,
+            is_main: false,
+            is_current: false,
+            operation: None,
+            created: None,
+        }
+    }
+
+    fn commit_on(path: &Path, message: &str) {
+        let repo = Repository::open(path).unwrap();
+        let sig = git2::Signature::now("test", "test@example.com").unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let tree = head.tree().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[&head])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_age_units() {
+        assert_eq!(parse_age("90m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_age("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_age("30").unwrap(), Duration::days(30));
+        assert_eq!(parse_age("2w").unwrap(), Duration::weeks(2));
+        assert!(parse_age("soon").is_err());
+        assert!(parse_age("3y").is_err());
+    }
+
+    #[test]
+    fn test_merged_and_unmerged_worktrees() {
+        let (dir, repo) = init_repo();
+        let head = repo.head().unwrap().target().unwrap();
+        repo.reference("refs/remotes/origin/main", head, true, "test")
+            .unwrap();
+
+        let merged = dir.path().join("merged");
+        add_worktree(&repo, "merged", None, false, &merged).unwrap();
+        let wip = dir.path().join("wip");
+        add_worktree(&repo, "wip", None, false, &wip).unwrap();
+        commit_on(&wip, "work in progress");
+        let fresh = dir.path().join("fresh");
+        add_worktree(&repo, "fresh", None, false, &fresh).unwrap();
+
+        // A branch with nothing on it yet is not merged, not even once the base moves on
+        let kept = find_candidate(
+            &repo,
+            entry("fresh", &fresh, WorktreeState::Branch),
+            "origin/main",
+            None,
+        );
+        assert!(kept.is_none());
+
+        // "merged" lands on origin/main, which then moves past it
+        commit_on(&merged, "done");
+        let done = repo.find_branch("merged", BranchType::Local).unwrap();
+        let done = done.get().peel_to_commit().unwrap();
+        let sig = git2::Signature::now("test", "test@example.com").unwrap();
+        let after = repo
+            .commit(None, &sig, &sig, "after", &done.tree().unwrap(), &[&done])
+            .unwrap();
+        repo.reference("refs/remotes/origin/main", after, true, "test")
+            .unwrap();
+        let kept = find_candidate(
+            &repo,
+            entry("fresh", &fresh, WorktreeState::Branch),
+            "origin/main",
+            None,
+        );
+        assert!(kept.is_none());
+
+        let candidate = find_candidate(
+            &repo,
+            entry("merged", &merged, WorktreeState::Branch),
+            "origin/main",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            candidate.reasons,
+            vec![PruneReason::Merged("origin/main".to_string())]
+        );
+
+        let kept = find_candidate(
+            &repo,
+            entry("wip", &wip, WorktreeState::Branch),
+            "origin/main",
+            None,
+        );
+        assert!(kept.is_none());
+
+        // Everything is old once the threshold is negative
+        let stale = find_candidate(
+            &repo,
+            entry("wip", &wip, WorktreeState::Branch),
+            "origin/main",
+            Some(Duration::days(-1)),
+        )
+        .unwrap();
+        assert!(matches!(stale.reasons[..], [PruneReason::Stale(_)]));
+    }
+
+    #[test]
+    fn test_gone_upstream_and_missing_directory() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().join("topic");
+        add_worktree(&repo, "topic", None, false, &path).unwrap();
+        commit_on(&path, "unmerged");
+        repo.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("branch.topic.remote", "origin").unwrap();
+        config
+            .set_str("branch.topic.merge", "refs/heads/topic")
+            .unwrap();
+        fs::remove_dir_all(&path).unwrap();
+
+        let candidate = find_candidate(
+            &repo,
+            entry("topic", &path, WorktreeState::Missing),
+            "origin/main",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            candidate.reasons,
+            vec![
+                PruneReason::MissingDirectory,
+                PruneReason::UpstreamGone("origin/topic".to_string())
+            ]
+        );
+        assert_ne!(candidate.updated, "unknown");
+    }
+
+    #[test]
+    fn test_format_candidates_lists_reasons_and_paths() {
+        let candidate = PruneCandidate {
+            entry: entry(
+                "topic",
+                Path::new("/repo/worktrees/topic"),
+                WorktreeState::Missing,
+            ),
+            reasons: vec![
+                PruneReason::MissingDirectory,
+                PruneReason::Merged("origin/main".to_string()),
+            ],
+            updated: "3d ago".to_string(),
+        };
+        let table = format_candidates(&[candidate]);
+        assert!(table.starts_with("Branch  Updated"));
+        assert!(table.contains("directory missing, merged into origin/main"));
+        assert!(table.contains("/repo/worktrees/topic"));
+    }
+}
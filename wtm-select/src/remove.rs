@@ -0,0 +1,275 @@
+use crate::cache::MetadataCache;
+use crate::hooks::{HookEvent, HookRunner};
+use crate::list::{WorktreeInfo, collect_info};
+use crate::picker::{LayoutArgs, PickerOptions, Selection, select_worktree};
+use crate::theme::warning_prefix;
+use crate::worktree::{
+    WorktreeEntry, check_removal, collect_worktrees, ensure_removable, format_risks,
+    open_current_repo, prepare_removal,
+};
+use anyhow::{Result, bail};
+use clap::Args as ClapArgs;
+use rayon::prelude::*;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+/// Arguments for `wtm-select remove`
+#[derive(ClapArgs, Debug)]
+pub struct RemoveArgs {
+    /// Branch whose worktree should be removed (omit to pick interactively)
+    pub branch: Option<String>,
+
+    /// Show preview panel in the interactive picker
+    #[arg(long)]
+    pub preview: bool,
+
+    /// Plain text instead of emoji in the picker (same as `theme.ascii: true`)
+    #[arg(long, visible_alias = "ascii")]
+    pub no_emoji: bool,
+
+    #[command(flatten)]
+    pub layout: LayoutArgs,
+
+    /// Do not ask for confirmation before removing
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+
+    /// Remove even if uncommitted changes, untracked files or unpushed commits would be lost
+    #[arg(short = 'f', long)]
+    pub force: bool,
+}
+
+/// Entry point for the `remove` subcommand
+pub fn run(args: RemoveArgs) -> Result<ExitCode> {
+    let Some(branch) = args.branch else {
+        // The main and current worktrees can never be removed, so don't offer them
+        let opts = PickerOptions {
+            prompt: "Remove worktrees (Tab to mark) > ",
+            prompt_icon: "🗑",
+            preview: args.preview,
+            no_emoji: args.no_emoji,
+            layout: args.layout,
+            multi: true,
+            removable_only: true,
+            ..Default::default()
+        };
+        return match select_worktree(opts)? {
+            Selection::Selected(selected) => remove_selected(selected, args.yes, args.force),
+            Selection::Aborted => Ok(ExitCode::from(crate::EXIT_ABORTED)),
+        };
+    };
+
+    let Some(entry) = collect_worktrees()?
+        .into_iter()
+        .find(|entry| entry.branch == branch)
+    else {
+        bail!("No worktree for branch: {branch}");
+    };
+    let hooks = HookRunner::for_repo(&open_current_repo()?)?;
+    remove_with_hooks(&hooks, &branch, &entry.path, args.force)?;
+    eprintln!("Removed worktree & branch: {branch}");
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Confirm and remove every worktree marked in the picker, skipping those that would
+/// lose work unless `force` is set
+pub fn remove_selected(selected: Vec<WorktreeEntry>, yes: bool, force: bool) -> Result<ExitCode> {
+    let cache = MetadataCache::load(&open_current_repo()?);
+    let infos: Vec<WorktreeInfo> = selected
+        .into_par_iter()
+        .map(|entry| collect_info(entry, &cache))
+        .collect();
+
+    let (infos, skipped) = split_risky(infos, force)?;
+    if infos.is_empty() && skipped > 0 {
+        bail!("{skipped} worktree(s) would lose work; re-run with --force to remove anyway");
+    }
+
+    eprint!("{}", format_removal_summary(&infos));
+    if !yes && !confirm(&format!("Remove {} worktree(s)?", infos.len()))? {
+        eprintln!("Aborted");
+        return Ok(ExitCode::from(crate::EXIT_ABORTED));
+    }
+
+    let hooks = HookRunner::for_repo(&open_current_repo()?)?;
+    let mut failed = 0;
+    for info in &infos {
+        if let Err(e) = remove_with_hooks(&hooks, &info.branch, &info.path, force) {
+            eprintln!("Failed to remove {}: {e:#}", info.branch);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "{failed} of {} worktree(s) could not be removed",
+            infos.len()
+        );
+    }
+    if skipped > 0 {
+        eprintln!("Kept {skipped} worktree(s) that would lose work; use --force to remove them");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Pre-flight check of every worktree before anything is deleted: those that would lose
+/// work are dropped (with a warning) unless `force` is set; returns the rest and how many
+/// were dropped
+fn split_risky(infos: Vec<WorktreeInfo>, force: bool) -> Result<(Vec<WorktreeInfo>, usize)> {
+    let mut removable = Vec::new();
+    let mut skipped = 0;
+    for info in infos {
+        ensure_removable(&info.path)?;
+        let risks = check_removal(&info.branch, &info.path, &info.base_branch)?;
+        if risks.is_empty() || force {
+            removable.push(info);
+        } else {
+            eprintln!(
+                "{}Skipping {}: {}",
+                warning_prefix(),
+                info.branch,
+                format_risks(&risks)
+            );
+            skipped += 1;
+        }
+    }
+    Ok((removable, skipped))
+}
+
+/// Remove a worktree and its branch between its `pre-remove` and `post-remove` hooks.
+///
+/// The hooks only run for a removal that passed its checks.
+fn remove_with_hooks(hooks: &HookRunner, branch: &str, path: &str, force: bool) -> Result<()> {
+    let removal = prepare_removal(branch, path, force)?;
+    hooks.run(HookEvent::PreRemove, Path::new(path), branch)?;
+    removal.execute()?;
+    hooks.run(HookEvent::PostRemove, Path::new(path), branch)
+}
+
+/// Describe what is about to be removed: branch, dirty files and merge state
+pub fn format_removal_summary(infos: &[WorktreeInfo]) -> String {
+    let width = infos.iter().map(|i| i.branch.len()).max().unwrap_or(0);
+    let mut output = String::from("The following worktrees will be removed:\n");
+
+    for info in infos {
+        let dirty = match info.dirty {
+            0 => "clean".to_string(),
+            n => format!("{n} dirty file(s)"),
+        };
+        let merged = match info.ahead {
+            Some(0) => format!("merged into {}", info.base_branch),
+            Some(n) => format!("NOT merged ({n} commit(s) ahead of {})", info.base_branch),
+            None => format!("merge state unknown ({} not found)", info.base_branch),
+        };
+        output.push_str(&format!(
+            "  {:<width$}  {dirty:<18} {merged}\n    {}\n",
+            info.branch, info.path
+        ));
+    }
+    output
+}
+
+/// Ask a yes/no question on stderr; anything but y/yes is a no
+fn confirm(question: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        bail!("Refusing to remove without confirmation; pass --yes to skip the prompt");
+    }
+
+    eprint!("{question} [y/N] ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add::add_worktree;
+    use crate::add::tests::init_repo;
+    use std::fs;
+
+    fn info(branch: &str, dirty: usize, ahead: Option<usize>) -> WorktreeInfo {
+        WorktreeInfo {
+            branch: branch.to_string(),
+            path: format!("/repo/worktrees/{branch}"),
+            dirname: branch.to_string(),
+            state: "branch".to_string(),
+            state_detail: None,
+            head: None,
+            last_commit: None,
+            dirty,
+            base_branch: "origin/main".to_string(),
+            ahead,
+            behind: None,
+        }
+    }
+
+    #[test]
+    fn test_risky_worktrees_are_skipped_unless_forced() {
+        let (dir, repo) = init_repo();
+        let clean = dir.path().join("clean");
+        add_worktree(&repo, "clean", None, false, &clean).unwrap();
+        let wip = dir.path().join("wip");
+        add_worktree(&repo, "wip", None, false, &wip).unwrap();
+        fs::write(wip.join("notes.txt"), "draft").unwrap();
+        let at = |branch: &str, path: &Path| WorktreeInfo {
+            path: path.to_string_lossy().to_string(),
+            base_branch: "main".to_string(),
+            ..info(branch, 0, Some(0))
+        };
+        let infos = vec![at("clean", &clean), at("wip", &wip)];
+
+        let (kept, skipped) = split_risky(infos.clone(), false).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].branch, "clean");
+
+        let (kept, skipped) = split_risky(infos, true).unwrap();
+        assert_eq!((kept.len(), skipped), (2, 0));
+    }
+
+    #[test]
+    fn test_refused_removal_never_runs_the_hook() {
+        let (dir, repo) = init_repo();
+        let wip = dir.path().join("wip");
+        add_worktree(&repo, "wip", None, false, &wip).unwrap();
+        fs::write(wip.join("notes.txt"), "draft").unwrap();
+        let marker = dir.path().join("hook-ran");
+        let config = serde_yaml::from_str(&format!(
+            "hooks:\n  pre-remove: touch {}\n",
+            marker.display()
+        ))
+        .unwrap();
+        let hooks = HookRunner::new(dir.path().to_path_buf(), config);
+        let path = wip.to_str().unwrap();
+
+        assert!(remove_with_hooks(&hooks, "wip", path, false).is_err());
+        assert!(!marker.exists());
+        assert!(wip.exists());
+
+        remove_with_hooks(&hooks, "wip", path, true).unwrap();
+        assert!(marker.exists());
+        assert!(!wip.exists());
+    }
+
+    #[test]
+    fn test_removal_summary_lists_every_worktree() {
+        let summary = format_removal_summary(&[
+            info("merged", 0, Some(0)),
+            info("wip", 2, Some(3)),
+            info("orphan", 0, None),
+        ]);
+
+        assert!(summary.contains("merged into origin/main"));
+        assert!(summary.contains("2 dirty file(s)"));
+        assert!(summary.contains("NOT merged (3 commit(s) ahead of origin/main)"));
+        assert!(summary.contains("merge state unknown"));
+        assert!(summary.contains("/repo/worktrees/orphan"));
+    }
+}
@@ -0,0 +1,115 @@
+use git2::Repository;
+
+/// An entry of `git stash list`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stash {
+    /// Position in the stash list, as in `stash@{index}`
+    pub index: usize,
+    /// Branch the stash was made on, `None` when it was made on a detached HEAD
+    pub branch: Option<String>,
+    /// The stash message without the `WIP on <branch>:` prefix
+    pub message: String,
+    /// Unix timestamp of the stash commit
+    pub time: Option<i64>,
+}
+
+/// All stashes of the repository owning `repo`, newest first.
+///
+/// Stashes are shared by all worktrees; `Stash::branch` tells which one they belong to.
+pub fn list_stashes(repo: &Repository) -> Vec<Stash> {
+    // `stash_foreach` needs a mutable handle, so use one of our own
+    let Ok(mut shared) = Repository::open(repo.commondir()) else {
+        return Vec::new();
+    };
+
+    let mut stashes = Vec::new();
+    let _ = shared.stash_foreach(|index, message, oid| {
+        let (branch, message) = parse_stash_message(message);
+        stashes.push((index, branch, message, *oid));
+        true
+    });
+
+    stashes
+        .into_iter()
+        .map(|(index, branch, message, oid)| Stash {
+            index,
+            branch,
+            message,
+            time: shared
+                .find_commit(oid)
+                .ok()
+                .map(|commit| commit.time().seconds()),
+        })
+        .collect()
+}
+
+/// Stashes made on `branch`
+pub fn stashes_for_branch<'s>(stashes: &'s [Stash], branch: &str) -> Vec<&'s Stash> {
+    stashes
+        .iter()
+        .filter(|stash| stash.branch.as_deref() == Some(branch))
+        .collect()
+}
+
+/// Split `WIP on topic: 1a2b3c4 subject` or `On topic: message` into the branch and the rest
+fn parse_stash_message(message: &str) -> (Option<String>, String) {
+    let Some(rest) = message
+        .strip_prefix("WIP on ")
+        .or_else(|| message.strip_prefix("On "))
+    else {
+        return (None, message.to_string());
+    };
+    match rest.split_once(": ") {
+        Some(("(no branch)", text)) => (None, text.to_string()),
+        Some((branch, text)) => (Some(branch.to_string()), text.to_string()),
+        None => (None, message.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add::tests::init_repo;
+    use git2::Signature;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_stash_message() {
+        assert_eq!(
+            parse_stash_message("WIP on topic: 1a2b3c4 Add parser"),
+            (Some("topic".to_string()), "1a2b3c4 Add parser".to_string())
+        );
+        assert_eq!(
+            parse_stash_message("On feature/x: before rebase"),
+            (Some("feature/x".to_string()), "before rebase".to_string())
+        );
+        assert_eq!(
+            parse_stash_message("WIP on (no branch): 1a2b3c4 Add parser"),
+            (None, "1a2b3c4 Add parser".to_string())
+        );
+        assert_eq!(
+            parse_stash_message("autostash"),
+            (None, "autostash".to_string())
+        );
+    }
+
+    #[test]
+    fn test_stashes_are_attributed_to_their_branch() {
+        let (dir, mut repo) = init_repo();
+        let sig = Signature::now("test", "test@example.com").unwrap();
+        fs::write(dir.path().join("notes.txt"), "one").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("notes.txt")).unwrap();
+        index.write().unwrap();
+        repo.stash_save(&sig, "halfway", None).unwrap();
+
+        let stashes = list_stashes(&repo);
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].index, 0);
+        assert_eq!(stashes[0].message, "halfway");
+        assert!(stashes[0].time.is_some());
+        assert_eq!(stashes_for_branch(&stashes, "main").len(), 1);
+        assert!(stashes_for_branch(&stashes, "topic").is_empty());
+    }
+}
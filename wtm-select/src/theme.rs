@@ -0,0 +1,234 @@
+use crate::config::load_layered_config;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const RESET: &str = "\x1b[0m";
+
+/// Colors of the picker and its preview (`theme:` in wt.config.yml)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Plain text instead of emoji (also `--no-emoji`)
+    pub ascii: bool,
+    /// Picker colors in skim's `--color` format
+    pub picker: String,
+    /// Section titles and file headers of diffs
+    pub heading: Style,
+    /// Commit ids
+    pub hash: Style,
+    /// Relative times such as `2h ago`
+    pub time: Style,
+    /// Index column of `git status --short` codes
+    pub staged: Style,
+    /// Working tree column of `git status --short` codes
+    pub unstaged: Style,
+    pub untracked: Style,
+    pub conflicted: Style,
+    /// Added lines and files, insertions
+    pub added: Style,
+    /// Deleted lines and files, deletions
+    pub deleted: Style,
+    /// Modified, renamed and copied files
+    pub modified: Style,
+    /// `@@ -1,2 +1,3 @@` lines of diffs
+    pub hunk: Style,
+    /// In-progress operations and config problems
+    pub warning: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let style = |spec: &str| Style::parse(spec).unwrap();
+        Self {
+            ascii: false,
+            picker: "matched:bg:yellow,matched:fg:black".to_string(),
+            heading: style("bold"),
+            hash: style("yellow"),
+            time: style("cyan"),
+            staged: style("green"),
+            unstaged: style("red"),
+            untracked: style("red"),
+            conflicted: style("bold red"),
+            added: style("green"),
+            deleted: style("red"),
+            modified: style("yellow"),
+            hunk: style("cyan"),
+            warning: style("bold yellow"),
+        }
+    }
+}
+
+impl Theme {
+    /// `icon` followed by a space, or nothing in ascii mode
+    pub fn icon(&self, icon: &str) -> String {
+        if self.ascii {
+            String::new()
+        } else {
+            format!("{icon} ")
+        }
+    }
+
+    /// `emoji`, or `ascii` in ascii mode
+    pub fn symbol<'a>(&self, emoji: &'a str, ascii: &'a str) -> &'a str {
+        if self.ascii { ascii } else { emoji }
+    }
+
+    /// A section title: icon, bold text and a rule
+    pub fn section(&self, icon: &str, title: &str) -> String {
+        format!(
+            "{}{}\n───────────────────────────────────────────────────\n",
+            self.icon(icon),
+            self.heading.paint(title)
+        )
+    }
+}
+
+/// `--no-emoji` was given
+static NO_EMOJI: AtomicBool = AtomicBool::new(false);
+
+/// Use plain text in messages printed outside the picker, which have no `Theme` at hand
+pub fn set_no_emoji() {
+    NO_EMOJI.store(true, Ordering::Relaxed);
+}
+
+/// Whether messages on stderr avoid emoji: `--no-emoji`, or `theme.ascii` in the config
+/// of the repository in the current directory
+pub fn ascii_messages() -> bool {
+    static CONFIGURED: OnceLock<bool> = OnceLock::new();
+    NO_EMOJI.load(Ordering::Relaxed)
+        || *CONFIGURED.get_or_init(|| {
+            env::current_dir().is_ok_and(|dir| load_layered_config(&dir).config.theme.ascii)
+        })
+}
+
+/// Start of a warning on stderr
+pub fn warning_prefix() -> &'static str {
+    warning_prefix_for(ascii_messages())
+}
+
+/// `warning_prefix` in ascii mode or not
+fn warning_prefix_for(ascii: bool) -> &'static str {
+    if ascii { "warning: " } else { "⚠️  " }
+}
+
+/// Text attributes and a foreground color, written like `bold red`, `bright-blue`,
+/// `208` or `#ff8800`; `none` or an empty string for plain text
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Style {
+    spec: String,
+    /// SGR parameters, e.g. `1;31`
+    sgr: String,
+}
+
+impl Style {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut codes = Vec::new();
+        for word in spec.split_whitespace() {
+            let code = match word {
+                "none" => continue,
+                "bold" => "1".to_string(),
+                "dim" => "2".to_string(),
+                "italic" => "3".to_string(),
+                "underline" => "4".to_string(),
+                _ => color_code(word).ok_or_else(|| {
+                    format!(
+                        "invalid style `{word}` in `{spec}`: expected bold, dim, italic, \
+                         underline, a color name, a number from 0 to 255 or #rrggbb"
+                    )
+                })?,
+            };
+            codes.push(code);
+        }
+        Ok(Self {
+            spec: spec.to_string(),
+            sgr: codes.join(";"),
+        })
+    }
+
+    /// `text` wrapped in the ANSI escapes of this style
+    pub fn paint(&self, text: impl fmt::Display) -> String {
+        if self.sgr.is_empty() {
+            text.to_string()
+        } else {
+            format!("\x1b[{}m{text}{RESET}", self.sgr)
+        }
+    }
+}
+
+/// SGR code of a foreground color
+fn color_code(word: &str) -> Option<String> {
+    const NAMES: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    ];
+    if let Some(name) = word.strip_prefix("bright-") {
+        let index = NAMES.iter().position(|&n| n == name)?;
+        return Some((90 + index).to_string());
+    }
+    if let Some(index) = NAMES.iter().position(|&n| n == word) {
+        return Some((30 + index).to_string());
+    }
+    if let Ok(index) = word.parse::<u8>() {
+        return Some(format!("38;5;{index}"));
+    }
+    let hex = word.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(format!(
+        "38;2;{};{};{}",
+        channel(0)?,
+        channel(2)?,
+        channel(4)?
+    ))
+}
+
+impl TryFrom<String> for Style {
+    type Error = String;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        Self::parse(&spec)
+    }
+}
+
+impl From<Style> for String {
+    fn from(style: Style) -> Self {
+        style.spec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_parse() {
+        assert_eq!(Style::parse("bold red").unwrap().sgr, "1;31");
+        assert_eq!(Style::parse("bright-blue").unwrap().sgr, "94");
+        assert_eq!(Style::parse("208").unwrap().sgr, "38;5;208");
+        assert_eq!(Style::parse("#ff8800").unwrap().sgr, "38;2;255;136;0");
+        assert_eq!(Style::parse("none").unwrap().paint("x"), "x");
+        assert!(Style::parse("bold purple").is_err());
+        assert!(Style::parse("#ff88").is_err());
+    }
+
+    #[test]
+    fn test_theme_from_yaml() {
+        let theme: Theme = serde_yaml::from_str("ascii: true\nadded: bright-green\n").unwrap();
+        assert!(theme.ascii);
+        assert_eq!(theme.added.paint("+1"), "\x1b[92m+1\x1b[0m");
+        assert_eq!(theme.deleted, Theme::default().deleted);
+        assert_eq!(theme.icon("🌳"), "");
+        assert_eq!(Theme::default().icon("🌳"), "🌳 ");
+
+        let error = serde_yaml::from_str::<Theme>("added: greem\n").unwrap_err();
+        assert!(error.to_string().contains("invalid style `greem`"));
+    }
+
+    #[test]
+    fn test_ascii_mode_turns_warnings_into_text() {
+        assert_eq!(warning_prefix_for(true), "warning: ");
+        assert_eq!(warning_prefix_for(false), "⚠️  ");
+    }
+}
@@ -0,0 +1,1240 @@
+use crate::config::load_config;
+use anyhow::{Context, Result, bail};
+use chrono::{Local, TimeZone};
+use git2::{
+    BranchType, Commit, Oid, Repository, RepositoryState, Status, StatusOptions, Worktree,
+    WorktreeLockStatus, WorktreePruneOptions,
+};
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+pub fn format_relative_time(dt: &chrono::DateTime<Local>) -> String {
+    let now = Local::now();
+    let duration = now.signed_duration_since(*dt);
+
+    if duration.num_days() > 0 {
+        format!("{}d ago", duration.num_days())
+    } else if duration.num_hours() > 0 {
+        format!("{}h ago", duration.num_hours())
+    } else if duration.num_minutes() > 0 {
+        format!("{}m ago", duration.num_minutes())
+    } else {
+        "now".to_string()
+    }
+}
+
+pub fn get_last_commit_info(path: &str) -> (Option<i64>, String) {
+    let repo = match Repository::open(path) {
+        Ok(r) => r,
+        Err(_) => return (None, "unknown".to_string()),
+    };
+
+    let head = match repo.head() {
+        Ok(h) => h,
+        Err(_) => return (None, "unknown".to_string()),
+    };
+
+    let commit = match head.peel_to_commit() {
+        Ok(c) => c,
+        Err(_) => return (None, "unknown".to_string()),
+    };
+
+    let timestamp = commit.time().seconds();
+    (Some(timestamp), relative_time(timestamp))
+}
+
+/// Format a unix timestamp like `format_relative_time`
+pub fn relative_time(timestamp: i64) -> String {
+    let dt = Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .unwrap_or_else(Local::now);
+    format_relative_time(&dt)
+}
+
+/// Commit time of a worktree's HEAD, read from the shared object database.
+///
+/// Unlike `get_last_commit_info` this never touches the worktree directory,
+/// so it stays fast when worktrees live on a slow filesystem.
+pub fn head_commit_time(main_repo: &Repository, entry: &WorktreeEntry) -> Option<i64> {
+    let commit = match &entry.state {
+        WorktreeState::Detached(oid) => main_repo.revparse_single(oid).ok()?.peel_to_commit(),
+        _ => main_repo
+            .find_branch(&entry.branch, BranchType::Local)
+            .ok()?
+            .get()
+            .peel_to_commit(),
+    };
+    Some(commit.ok()?.time().seconds())
+}
+
+pub fn get_dirname(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// Join risks into a single human readable line
+pub fn format_risks(risks: &[RemovalRisk]) -> String {
+    risks
+        .iter()
+        .map(|r| r.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Resolve `base_branch` from config to a commit.
+///
+/// Short names such as `origin/main` are looked up under `refs/remotes/`.
+pub fn find_base_commit<'r>(repo: &'r Repository, base_branch: &str) -> Option<Commit<'r>> {
+    // Convert base_branch to ref format (e.g., "origin/main" -> "refs/remotes/origin/main");
+    // a name that is not a remote-tracking branch may still be a local one
+    let candidates = if base_branch.starts_with("refs/") {
+        vec![base_branch.to_string()]
+    } else {
+        vec![
+            format!("refs/remotes/{base_branch}"),
+            format!("refs/heads/{base_branch}"),
+        ]
+    };
+
+    candidates
+        .iter()
+        .find_map(|base_ref| repo.find_reference(base_ref).ok())?
+        .peel_to_commit()
+        .ok()
+}
+
+/// Used when nothing better can be detected
+pub const FALLBACK_BASE_BRANCH: &str = "origin/main";
+
+/// Names tried when the remote has no HEAD and `init.defaultBranch` does not exist
+const COMMON_BASE_BRANCHES: [&str; 4] = ["main", "master", "develop", "trunk"];
+
+/// The repository's default branch: where `refs/remotes/<remote>/HEAD` points, else
+/// `init.defaultBranch`, else the first of main/master/develop/trunk that exists.
+///
+/// The remote is `origin` when there is one, else the first remote. Remote-tracking
+/// branches are preferred over local ones.
+pub fn detect_base_branch(repo: &Repository) -> String {
+    let remote = repo.remotes().ok().and_then(|remotes| {
+        let names: Vec<&str> = remotes.iter().flatten().collect();
+        names
+            .iter()
+            .find(|&&name| name == "origin")
+            .or(names.first())
+            .map(|name| name.to_string())
+    });
+
+    if let Some(remote) = &remote
+        && let Ok(head) = repo.find_reference(&format!("refs/remotes/{remote}/HEAD"))
+        && let Some(target) = head.symbolic_target()
+        && let Some(branch) = target.strip_prefix("refs/remotes/")
+    {
+        return branch.to_string();
+    }
+
+    let configured = repo
+        .config()
+        .and_then(|config| config.get_string("init.defaultBranch"))
+        .ok();
+    for name in configured
+        .iter()
+        .map(String::as_str)
+        .chain(COMMON_BASE_BRANCHES)
+    {
+        if let Some(remote) = &remote {
+            let tracking = format!("{remote}/{name}");
+            if repo.find_branch(&tracking, BranchType::Remote).is_ok() {
+                return tracking;
+            }
+        }
+        if repo.find_branch(name, BranchType::Local).is_ok() {
+            return name.to_string();
+        }
+    }
+
+    FALLBACK_BASE_BRANCH.to_string()
+}
+
+/// The local branch `branch` is stacked on: the one it has the fewest own commits on top
+/// of, counted from their merge base. `None` when no branch is closer than `base_branch`.
+pub fn find_parent_branch(repo: &Repository, branch: &str, base_branch: &str) -> Option<String> {
+    let head = repo
+        .find_branch(branch, BranchType::Local)
+        .ok()?
+        .get()
+        .target()?;
+    let own_commits = |tip: Oid| repo.graph_ahead_behind(head, tip).ok().map(|(a, _)| a);
+    let mut best = find_base_commit(repo, base_branch)
+        .and_then(|base| own_commits(base.id()))
+        .unwrap_or(usize::MAX);
+
+    let mut parent = None;
+    for (candidate, _) in repo.branches(Some(BranchType::Local)).ok()?.flatten() {
+        let (Ok(Some(name)), Some(tip)) = (candidate.name(), candidate.get().target()) else {
+            continue;
+        };
+        if name == branch || tip == head {
+            continue;
+        }
+        // Zero means the candidate builds on this branch rather than the other way round
+        if let Some(ahead) = own_commits(tip)
+            && ahead > 0
+            && ahead < best
+        {
+            best = ahead;
+            parent = Some(name.to_string());
+        }
+    }
+    parent
+}
+
+/// Name of the branch checked out in `repo`, empty when HEAD is detached or unborn
+pub fn head_branch_name(repo: &Repository) -> String {
+    repo.head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Whether every commit on `head_oid` is already on `base_branch`.
+///
+/// `None` when the base branch cannot be resolved or has no common history.
+pub fn is_merged_into(repo: &Repository, head_oid: Oid, base_branch: &str) -> Option<bool> {
+    let base_commit = find_base_commit(repo, base_branch)?;
+    let merge_base = repo.merge_base(head_oid, base_commit.id()).ok()?;
+    Some(merge_base == head_oid)
+}
+
+/// Commits ahead of / behind `base_branch` for the worktree's HEAD
+pub fn base_ahead_behind(repo: &Repository, base_branch: &str) -> Option<(usize, usize)> {
+    let head_oid = repo.head().ok()?.target()?;
+    let base_commit = find_base_commit(repo, base_branch)?;
+    repo.graph_ahead_behind(head_oid, base_commit.id()).ok()
+}
+
+/// Merge base of HEAD and `base_branch`, where `git diff base...HEAD` starts; the base
+/// tip itself when the histories are unrelated
+pub fn find_fork_point<'r>(repo: &'r Repository, base_branch: &str) -> Option<Commit<'r>> {
+    let base_commit = find_base_commit(repo, base_branch)?;
+    let head_oid = repo.head().ok()?.target()?;
+    let fork_point = repo
+        .merge_base(head_oid, base_commit.id())
+        .ok()
+        .and_then(|oid| repo.find_commit(oid).ok());
+    Some(fork_point.unwrap_or(base_commit))
+}
+
+/// Commits ahead of / behind the upstream of the checked-out branch
+pub fn upstream_ahead_behind(repo: &Repository) -> Option<(usize, usize)> {
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+    let upstream = repo
+        .find_branch(head.shorthand()?, BranchType::Local)
+        .ok()?
+        .upstream()
+        .ok()?;
+    repo.graph_ahead_behind(head.target()?, upstream.get().target()?)
+        .ok()
+}
+
+/// Open the repository containing the current directory (or `$GIT_DIR`)
+pub fn open_current_repo() -> Result<Repository> {
+    Repository::open_from_env()
+        .or_else(|_| Repository::discover("."))
+        .context("Failed to open git repository")
+}
+
+/// What kind of checkout a worktree is, shown as a badge in the picker and `list`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorktreeState {
+    /// A branch is checked out
+    Branch,
+    /// HEAD points directly at a commit (short id)
+    Detached(String),
+    /// Protected from pruning, with the optional reason given to `git worktree lock`
+    Locked(Option<String>),
+    /// The admin entry is broken (e.g. the `.git` file is gone) but the directory exists
+    Prunable,
+    /// The worktree directory no longer exists
+    Missing,
+    /// The main repository is bare
+    Bare,
+}
+
+impl WorktreeState {
+    /// Machine-readable name of the state
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Branch => "branch",
+            Self::Detached(_) => "detached",
+            Self::Locked(_) => "locked",
+            Self::Prunable => "prunable",
+            Self::Missing => "missing",
+            Self::Bare => "bare",
+        }
+    }
+
+    /// Short label for the badge column (empty for ordinary branches)
+    pub fn badge(&self) -> &'static str {
+        match self {
+            Self::Branch => "",
+            _ => self.kind(),
+        }
+    }
+
+    /// Extra information: the commit for detached HEADs, the lock reason
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            Self::Detached(oid) => Some(oid),
+            Self::Locked(reason) => reason.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for WorktreeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Branch => write!(f, "branch"),
+            Self::Detached(oid) => write!(f, "detached@{oid}"),
+            Self::Locked(Some(reason)) => write!(f, "locked: {reason}"),
+            Self::Locked(None) => write!(f, "locked"),
+            Self::Prunable => write!(f, "prunable"),
+            Self::Missing => write!(f, "missing directory"),
+            Self::Bare => write!(f, "bare"),
+        }
+    }
+}
+
+/// A worktree known to the repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorktreeEntry {
+    /// Branch name, or `detached@<oid>` when HEAD is detached
+    pub branch: String,
+    pub path: String,
+    pub state: WorktreeState,
+    /// The main repository checkout (never removable)
+    pub is_main: bool,
+    /// The worktree containing the current working directory (never removable)
+    pub is_current: bool,
+    /// Rebase, merge etc. the worktree is in the middle of
+    pub operation: Option<Operation>,
+    /// When the worktree was added, read from its admin dir in the shared git dir
+    /// (`None` for the main worktree)
+    pub created: Option<i64>,
+}
+
+impl WorktreeEntry {
+    /// Whether `remove` may offer this worktree at all
+    pub fn is_removable(&self) -> bool {
+        !self.is_main && !self.is_current
+    }
+}
+
+/// A multi-step git command a worktree is in the middle of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    Bisect,
+    /// `git am`
+    ApplyMailbox,
+}
+
+impl Operation {
+    /// The operation `repo` is in the middle of, if any
+    pub fn of(repo: &Repository) -> Option<Self> {
+        match repo.state() {
+            RepositoryState::Clean => None,
+            RepositoryState::Merge => Some(Self::Merge),
+            RepositoryState::Rebase
+            | RepositoryState::RebaseInteractive
+            | RepositoryState::RebaseMerge
+            | RepositoryState::ApplyMailboxOrRebase => Some(Self::Rebase),
+            RepositoryState::CherryPick | RepositoryState::CherryPickSequence => {
+                Some(Self::CherryPick)
+            }
+            RepositoryState::Revert | RepositoryState::RevertSequence => Some(Self::Revert),
+            RepositoryState::Bisect => Some(Self::Bisect),
+            RepositoryState::ApplyMailbox => Some(Self::ApplyMailbox),
+        }
+    }
+
+    /// The operation the repository with git dir `git_dir` is in the middle of, from the
+    /// same marker files `Operation::of` goes by, without opening the repository
+    pub fn in_git_dir(git_dir: &Path) -> Option<Self> {
+        let exists = |name: &str| git_dir.join(name).exists();
+        if exists("rebase-merge") || exists("rebase-apply/rebasing") {
+            Some(Self::Rebase)
+        } else if exists("rebase-apply/applying") {
+            Some(Self::ApplyMailbox)
+        } else if exists("rebase-apply") {
+            Some(Self::Rebase)
+        } else if exists("MERGE_HEAD") {
+            Some(Self::Merge)
+        } else if exists("REVERT_HEAD") {
+            Some(Self::Revert)
+        } else if exists("CHERRY_PICK_HEAD") {
+            Some(Self::CherryPick)
+        } else if exists("BISECT_LOG") {
+            Some(Self::Bisect)
+        } else {
+            None
+        }
+    }
+
+    /// How to finish or get out of the operation
+    pub fn hint(self) -> String {
+        match self {
+            Self::Bisect => "git bisect reset".to_string(),
+            _ => format!("git {self} --continue / --abort"),
+        }
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Merge => "merge",
+            Self::Rebase => "rebase",
+            Self::CherryPick => "cherry-pick",
+            Self::Revert => "revert",
+            Self::Bisect => "bisect",
+            Self::ApplyMailbox => "am",
+        })
+    }
+}
+
+/// First 7 characters of an object id
+pub fn short_oid(oid: Oid) -> String {
+    oid.to_string()[..7].to_string()
+}
+
+/// Compare two paths after resolving symlinks, falling back to a plain comparison
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Working directory of the worktree the user is currently in, if any
+fn current_worktree_dir() -> Option<PathBuf> {
+    open_current_repo().ok()?.workdir().map(Path::to_path_buf)
+}
+
+pub fn collect_worktrees() -> Result<Vec<WorktreeEntry>> {
+    collect_worktrees_from(&open_current_repo()?)
+}
+
+/// Open the main repository (the one owning the common git dir) from any of its worktrees
+pub fn open_main_repo(repo: &Repository) -> Result<Repository> {
+    if !repo.is_worktree() {
+        return Repository::open(repo.path()).context("Failed to open main repository");
+    }
+    Repository::open(repo.commondir()).context("Failed to open main repository")
+}
+
+/// Working directory of the main repository, or `None` for bare repositories
+pub fn main_worktree_dir(main_repo: &Repository) -> Option<PathBuf> {
+    main_repo
+        .workdir()
+        .map(|dir| dir.components().collect::<PathBuf>())
+}
+
+/// Branch label and state for the HEAD of an openable repository
+fn head_label(repo: &Repository) -> (String, WorktreeState) {
+    match repo.head() {
+        Ok(head) if head.is_branch() => (
+            head.shorthand().unwrap_or("?").to_string(),
+            WorktreeState::Branch,
+        ),
+        Ok(head) => {
+            let oid = head.target().map(short_oid).unwrap_or_default();
+            (format!("detached@{oid}"), WorktreeState::Detached(oid))
+        }
+        // Unborn branch (no commits yet): HEAD is still a symbolic ref
+        Err(_) => (
+            repo.find_reference("HEAD")
+                .ok()
+                .and_then(|r| r.symbolic_target().map(str::to_string))
+                .map(|target| branch_from_ref(&target))
+                .unwrap_or_else(|| "(unknown)".to_string()),
+            WorktreeState::Branch,
+        ),
+    }
+}
+
+/// `refs/heads/foo` -> `foo`
+fn branch_from_ref(target: &str) -> String {
+    target
+        .strip_prefix("refs/heads/")
+        .unwrap_or(target)
+        .to_string()
+}
+
+/// Branch recorded in a worktree's admin dir, for entries that can no longer be opened
+fn admin_head_branch(main_repo: &Repository, name: &str) -> Option<String> {
+    let admin_dir = main_repo.path().join("worktrees").join(name);
+    admin_head(&admin_dir).map(|(branch, _)| branch)
+}
+
+/// Branch label and state for the HEAD file in a worktree's admin dir
+fn admin_head(admin_dir: &Path) -> Option<(String, WorktreeState)> {
+    let content = fs::read_to_string(admin_dir.join("HEAD")).ok()?;
+    let content = content.trim();
+    match content.strip_prefix("ref: ") {
+        Some(target) => Some((branch_from_ref(target), WorktreeState::Branch)),
+        None => {
+            let oid = content.get(..7)?.to_string();
+            Some((format!("detached@{oid}"), WorktreeState::Detached(oid)))
+        }
+    }
+}
+
+/// Find the linked worktree whose directory is `path`
+fn find_worktree_by_path(main_repo: &Repository, path: &Path) -> Result<Option<Worktree>> {
+    for name in main_repo.worktrees()?.iter().flatten() {
+        if let Ok(worktree) = main_repo.find_worktree(name)
+            && same_path(worktree.path(), path)
+        {
+            return Ok(Some(worktree));
+        }
+    }
+    Ok(None)
+}
+
+/// List the main repository and its linked worktrees, as seen from `current_repo`
+fn collect_worktrees_from(current_repo: &Repository) -> Result<Vec<WorktreeEntry>> {
+    let current_dir = current_repo.workdir().map(Path::to_path_buf);
+    let is_current = |path: &Path| {
+        current_dir
+            .as_deref()
+            .is_some_and(|dir| same_path(dir, path))
+    };
+
+    // Find the main repository first
+    let main_repo = open_main_repo(current_repo)?;
+
+    let mut worktrees = vec![main_entry(&main_repo, &is_current)];
+
+    // Linked worktrees, by admin name (which need not match the directory name).
+    // Each one costs a few reads of its directory, which add up on network file systems,
+    // so they are checked in parallel; Repository is not Sync, so each task opens its own.
+    let names: Vec<String> = main_repo
+        .worktrees()?
+        .iter()
+        .flatten()
+        .map(str::to_string)
+        .collect();
+    let main_path = main_repo.path().to_path_buf();
+    let linked: Vec<WorktreeEntry> = names
+        .par_iter()
+        .filter_map(|name| {
+            let main_repo = Repository::open(&main_path).ok()?;
+            linked_entry(&main_repo, name, is_current)
+        })
+        .collect();
+    worktrees.extend(linked);
+
+    Ok(worktrees)
+}
+
+/// Entry for the main worktree (or the bare repository)
+fn main_entry(main_repo: &Repository, is_current: &dyn Fn(&Path) -> bool) -> WorktreeEntry {
+    let (branch, head_state) = head_label(main_repo);
+    match main_worktree_dir(main_repo) {
+        Some(main_dir) => WorktreeEntry {
+            branch,
+            is_main: true,
+            is_current: is_current(&main_dir),
+            path: main_dir.to_string_lossy().to_string(),
+            state: head_state,
+            operation: Operation::of(main_repo),
+            created: None,
+        },
+        None => WorktreeEntry {
+            branch,
+            path: main_repo
+                .path()
+                .components()
+                .collect::<PathBuf>()
+                .to_string_lossy()
+                .to_string(),
+            state: WorktreeState::Bare,
+            is_main: true,
+            is_current: false,
+            operation: None,
+            created: None,
+        },
+    }
+}
+
+/// Like `collect_worktrees`, but reading only the shared git dir, so it never waits for
+/// a slow or unmounted worktree. Linked worktrees are never `Missing` or `Prunable`
+/// here; `checkout_state` finds out.
+pub fn collect_worktrees_quick() -> Result<Vec<WorktreeEntry>> {
+    collect_quick_from(&open_current_repo()?)
+}
+
+/// `collect_worktrees_quick` as seen from `current_repo`
+fn collect_quick_from(current_repo: &Repository) -> Result<Vec<WorktreeEntry>> {
+    let main_repo = open_main_repo(current_repo)?;
+    let current_dir = current_repo.workdir().map(Path::to_path_buf);
+    // The current worktree is recognized by its admin dir rather than by its path
+    let current_name = Worktree::open_from_repository(current_repo)
+        .ok()
+        .and_then(|worktree| worktree.name().map(str::to_string));
+
+    let main_is_current = |path: &Path| {
+        current_name.is_none()
+            && current_dir
+                .as_deref()
+                .is_some_and(|dir| same_path(dir, path))
+    };
+    let mut worktrees = vec![main_entry(&main_repo, &main_is_current)];
+
+    for name in main_repo.worktrees()?.iter().flatten() {
+        let Ok(worktree) = main_repo.find_worktree(name) else {
+            continue;
+        };
+        let admin_dir = main_repo.path().join("worktrees").join(name);
+        let (branch, head_state) = admin_head(&admin_dir)
+            .unwrap_or_else(|| ("(unknown)".to_string(), WorktreeState::Prunable));
+        let state = match worktree.is_locked() {
+            Ok(WorktreeLockStatus::Locked(reason)) => WorktreeState::Locked(reason),
+            _ => head_state,
+        };
+        worktrees.push(WorktreeEntry {
+            branch,
+            path: worktree.path().to_string_lossy().to_string(),
+            state,
+            is_main: false,
+            is_current: current_name.as_deref() == Some(name),
+            operation: Operation::in_git_dir(&admin_dir),
+            created: admin_created_time(&main_repo, name),
+        });
+    }
+    Ok(worktrees)
+}
+
+/// State of a worktree from `collect_worktrees_quick` once its directory has been looked
+/// at: `Missing` when it is gone, `Prunable` when it no longer is a checkout
+pub fn checkout_state(entry: &WorktreeEntry) -> WorktreeState {
+    if entry.is_main || matches!(entry.state, WorktreeState::Locked(_)) {
+        return entry.state.clone();
+    }
+    let path = Path::new(&entry.path);
+    if !path.exists() {
+        return WorktreeState::Missing;
+    }
+    match Repository::open(path) {
+        Ok(repo) if repo.is_worktree() => entry.state.clone(),
+        _ => WorktreeState::Prunable,
+    }
+}
+
+/// Entry for the linked worktree with admin name `name`
+fn linked_entry(
+    main_repo: &Repository,
+    name: &str,
+    is_current: impl Fn(&Path) -> bool,
+) -> Option<WorktreeEntry> {
+    let worktree = main_repo.find_worktree(name).ok()?;
+    let path = worktree.path();
+
+    let valid = worktree.validate().is_ok();
+    let (branch, head_state, operation) = match Repository::open_from_worktree(&worktree) {
+        Ok(wt_repo) if valid => {
+            let (branch, head_state) = head_label(&wt_repo);
+            (branch, head_state, Operation::of(&wt_repo))
+        }
+        _ => (
+            admin_head_branch(main_repo, name).unwrap_or_else(|| "(unknown)".to_string()),
+            WorktreeState::Prunable,
+            None,
+        ),
+    };
+
+    let state = if let Ok(WorktreeLockStatus::Locked(reason)) = worktree.is_locked() {
+        WorktreeState::Locked(reason)
+    } else if !path.exists() {
+        WorktreeState::Missing
+    } else {
+        head_state
+    };
+
+    Some(WorktreeEntry {
+        branch,
+        path: path.to_string_lossy().to_string(),
+        state,
+        is_main: false,
+        is_current: is_current(path),
+        operation,
+        created: admin_created_time(main_repo, name),
+    })
+}
+
+/// When the admin dir of worktree `name` was set up: the time its `commondir` file was
+/// written, which `git worktree add` does once and `git worktree move` leaves alone
+fn admin_created_time(main_repo: &Repository, name: &str) -> Option<i64> {
+    let file = main_repo
+        .path()
+        .join("worktrees")
+        .join(name)
+        .join("commondir");
+    let modified = fs::metadata(file).ok()?.modified().ok()?;
+    let secs = modified.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    i64::try_from(secs).ok()
+}
+
+/// Something that would be lost by removing a worktree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemovalRisk {
+    /// Staged or unstaged changes to tracked files
+    UncommittedChanges(usize),
+    /// Files git does not know about yet
+    UntrackedFiles(usize),
+    /// Commits not on the branch's upstream
+    UnpushedCommits { count: usize, upstream: String },
+    /// Commits on a branch without upstream that are not merged into the base branch
+    UnmergedCommits { count: usize, base_branch: String },
+}
+
+impl std::fmt::Display for RemovalRisk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UncommittedChanges(n) => write!(f, "{n} uncommitted change(s)"),
+            Self::UntrackedFiles(n) => write!(f, "{n} untracked file(s)"),
+            Self::UnpushedCommits { count, upstream } => {
+                write!(f, "{count} commit(s) not pushed to {upstream}")
+            }
+            Self::UnmergedCommits { count, base_branch } => {
+                write!(
+                    f,
+                    "{count} commit(s) without upstream, not merged into {base_branch}"
+                )
+            }
+        }
+    }
+}
+
+/// Refuse worktrees that must never be removed, regardless of `--force`
+pub fn ensure_removable(path: &str) -> Result<()> {
+    // A worktree whose directory is gone can't be the main or the current one
+    let Ok(repo) = Repository::open(path) else {
+        return Ok(());
+    };
+    if !repo.is_worktree() {
+        bail!(
+            "Refusing to remove {path}: it is the main repository, not a linked worktree \
+             (removing it would delete the repository itself)"
+        );
+    }
+    if current_worktree_dir().is_some_and(|dir| same_path(&dir, Path::new(path))) {
+        bail!(
+            "Refusing to remove {path}: it contains the current working directory \
+             (cd to another worktree first)"
+        );
+    }
+    Ok(())
+}
+
+/// Check whether removing the worktree at `path` would lose work.
+///
+/// Uses the same status options as the preview (untracked files included, ignored excluded).
+/// For stale worktrees only the branch's commits are checked, since the files are already gone.
+pub fn check_removal(branch: &str, path: &str, base_branch: &str) -> Result<Vec<RemovalRisk>> {
+    let Ok(repo) = Repository::open(path) else {
+        let main_repo = open_main_repo(&open_current_repo()?)?;
+        return Ok(main_repo
+            .find_branch(branch, BranchType::Local)
+            .ok()
+            .and_then(|b| b.get().target())
+            .and_then(|oid| commit_risk(&main_repo, branch, oid, base_branch))
+            .into_iter()
+            .collect());
+    };
+    let mut risks = Vec::new();
+
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(true);
+    let statuses = repo
+        .statuses(Some(&mut status_opts))
+        .context("Failed to read worktree status")?;
+    let untracked = statuses
+        .iter()
+        .filter(|e| e.status() == Status::WT_NEW)
+        .count();
+    let uncommitted = statuses.len() - untracked;
+    if uncommitted > 0 {
+        risks.push(RemovalRisk::UncommittedChanges(uncommitted));
+    }
+    if untracked > 0 {
+        risks.push(RemovalRisk::UntrackedFiles(untracked));
+    }
+
+    if let Ok(head) = repo.head()
+        && let Some(head_oid) = head.target()
+    {
+        let name = head.shorthand().filter(|_| head.is_branch()).unwrap_or("");
+        risks.extend(commit_risk(&repo, name, head_oid, base_branch));
+    }
+
+    Ok(risks)
+}
+
+/// Commits on `branch` (at `head_oid`) that exist nowhere else: not on its upstream,
+/// or, without an upstream, not on the base branch
+fn commit_risk(
+    repo: &Repository,
+    branch: &str,
+    head_oid: Oid,
+    base_branch: &str,
+) -> Option<RemovalRisk> {
+    let upstream = repo
+        .find_branch(branch, BranchType::Local)
+        .ok()
+        .and_then(|b| b.upstream().ok());
+
+    if let Some(upstream) = upstream {
+        let upstream_name = upstream.name().ok().flatten().unwrap_or("upstream");
+        let (ahead, _) = repo
+            .graph_ahead_behind(head_oid, upstream.get().target()?)
+            .ok()?;
+        (ahead > 0).then(|| RemovalRisk::UnpushedCommits {
+            count: ahead,
+            upstream: upstream_name.to_string(),
+        })
+    } else {
+        let base_commit = find_base_commit(repo, base_branch)?;
+        let (ahead, _) = repo.graph_ahead_behind(head_oid, base_commit.id()).ok()?;
+        (ahead > 0).then(|| RemovalRisk::UnmergedCommits {
+            count: ahead,
+            base_branch: base_branch.to_string(),
+        })
+    }
+}
+
+/// A worktree removal that has passed every check of `prepare_removal`
+pub struct Removal {
+    branch: String,
+    path: String,
+    worktree: Worktree,
+    main_repo: Repository,
+    /// Branch checked out in the worktree, deleted only when it is `branch`
+    head_branch: Option<String>,
+    /// Whether the directory is still a checkout, to be deleted along with the admin data
+    valid: bool,
+    /// Lock reason of a locked worktree removed with `force`
+    locked: Option<Option<String>>,
+}
+
+/// Check that a worktree and its local branch can be removed; `Removal::execute` then
+/// removes them (force-deleting the branch).
+///
+/// Unless `force` is set, refuses when `check_removal` reports any risk.
+/// The main and current worktrees are never removed, even with `force`.
+/// Worktrees whose directory is gone (or no longer a valid checkout) only have
+/// their admin data pruned; an existing directory is left alone in that case.
+pub fn prepare_removal(branch: &str, path: &str, force: bool) -> Result<Removal> {
+    ensure_removable(path)?;
+
+    // Open the worktree's admin entry (the name may differ from the dirname)
+    let (worktree, main_repo, head_branch, valid) = match Repository::open(path) {
+        Ok(repo) if repo.is_worktree() => {
+            let worktree =
+                Worktree::open_from_repository(&repo).context("Failed to open worktree")?;
+            let (head_branch, _) = head_label(&repo);
+            (worktree, open_main_repo(&repo)?, Some(head_branch), true)
+        }
+        _ => {
+            let main_repo = open_main_repo(&open_current_repo()?)?;
+            let worktree = find_worktree_by_path(&main_repo, Path::new(path))?
+                .ok_or_else(|| anyhow::anyhow!("{path} is not a worktree of this repository"))?;
+            let head_branch = worktree
+                .name()
+                .and_then(|name| admin_head_branch(&main_repo, name));
+            (worktree, main_repo, head_branch, false)
+        }
+    };
+
+    // A stale worktree has no config of its own; use the main repository's
+    let config_dir = match main_worktree_dir(&main_repo) {
+        Some(main_dir) if !valid => main_dir,
+        _ => PathBuf::from(path),
+    };
+    let base_branch = load_config(&config_dir).base_branch_for(&main_repo, branch);
+    let risks = check_removal(branch, path, &base_branch)?;
+    if !force && !risks.is_empty() {
+        bail!(
+            "Refusing to remove {branch} ({path}): {} (use --force to remove anyway)",
+            format_risks(&risks)
+        );
+    }
+
+    let locked = match worktree.is_locked()? {
+        WorktreeLockStatus::Locked(reason) => Some(reason),
+        WorktreeLockStatus::Unlocked => None,
+    };
+    if let Some(reason) = &locked
+        && !force
+    {
+        let reason = reason
+            .as_ref()
+            .map(|r| format!(" ({r})"))
+            .unwrap_or_default();
+        bail!("Refusing to remove {branch} ({path}): worktree is locked{reason}");
+    }
+
+    Ok(Removal {
+        branch: branch.to_string(),
+        path: path.to_string(),
+        worktree,
+        main_repo,
+        head_branch,
+        valid,
+        locked,
+    })
+}
+
+impl Removal {
+    /// Remove the worktree directory and its admin data, then its branch
+    pub fn execute(self) -> Result<()> {
+        let Self { branch, path, .. } = &self;
+        if let Some(reason) = &self.locked {
+            let reason = reason
+                .as_ref()
+                .map(|r| format!(" ({r})"))
+                .unwrap_or_default();
+            eprintln!("Removing locked worktree{reason}");
+        }
+
+        // Remove the worktree directory and its administrative files
+        eprintln!("Removing worktree: {branch} ({path})");
+        self.worktree
+            .prune(Some(
+                WorktreePruneOptions::new()
+                    .valid(true)
+                    .locked(self.locked.is_some())
+                    .working_tree(self.valid),
+            ))
+            .context("Failed to remove worktree")?;
+
+        // Delete the branch, but only if it really is the one checked out there
+        if self.head_branch.as_deref() == Some(branch.as_str())
+            && let Ok(mut branch_ref) = self.main_repo.find_branch(branch, BranchType::Local)
+        {
+            branch_ref.delete().context("Failed to delete branch")?;
+            eprintln!("Deleted branch: {branch}");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add::add_worktree;
+    use crate::add::tests::init_repo;
+    use std::fs;
+
+    #[test]
+    fn test_detect_base_branch_prefers_remote_head() {
+        let (_dir, repo) = init_repo();
+        let head = repo.head().unwrap().target().unwrap();
+        repo.remote("upstream", "https://example.com/repo.git")
+            .unwrap();
+        repo.reference("refs/remotes/upstream/develop", head, true, "test")
+            .unwrap();
+        repo.reference("refs/remotes/upstream/main", head, true, "test")
+            .unwrap();
+        assert_eq!(detect_base_branch(&repo), "upstream/main");
+
+        repo.reference_symbolic(
+            "refs/remotes/upstream/HEAD",
+            "refs/remotes/upstream/develop",
+            true,
+            "test",
+        )
+        .unwrap();
+        assert_eq!(detect_base_branch(&repo), "upstream/develop");
+    }
+
+    #[test]
+    fn test_detect_base_branch_without_remote() {
+        let (_dir, repo) = init_repo();
+        assert_eq!(detect_base_branch(&repo), "main");
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("trunk", &head, false).unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("init.defaultBranch", "trunk")
+            .unwrap();
+        assert_eq!(detect_base_branch(&repo), "trunk");
+
+        // A local base branch still resolves
+        assert_eq!(find_base_commit(&repo, "trunk").unwrap().id(), head.id());
+    }
+
+    #[test]
+    fn test_operation_in_progress() {
+        let (_dir, repo) = init_repo();
+        assert_eq!(Operation::of(&repo), None);
+
+        let head = repo.head().unwrap().target().unwrap();
+        fs::write(repo.path().join("MERGE_HEAD"), format!("{head}\n")).unwrap();
+        assert_eq!(Operation::of(&repo), Some(Operation::Merge));
+        assert_eq!(Operation::Merge.hint(), "git merge --continue / --abort");
+        fs::remove_file(repo.path().join("MERGE_HEAD")).unwrap();
+
+        fs::create_dir(repo.path().join("rebase-merge")).unwrap();
+        fs::write(repo.path().join("rebase-merge/interactive"), "").unwrap();
+        assert_eq!(Operation::of(&repo), Some(Operation::Rebase));
+    }
+
+    #[test]
+    fn test_find_parent_branch_picks_nearest_ancestor() {
+        let (_dir, repo) = init_repo();
+        let sig = git2::Signature::now("test", "test@example.com").unwrap();
+        let commit_on = |branch: &str, parent: Oid| {
+            let parent = repo.find_commit(parent).unwrap();
+            let oid = repo
+                .commit(
+                    None,
+                    &sig,
+                    &sig,
+                    branch,
+                    &parent.tree().unwrap(),
+                    &[&parent],
+                )
+                .unwrap();
+            repo.branch(branch, &repo.find_commit(oid).unwrap(), true)
+                .unwrap();
+            oid
+        };
+        let main = repo.head().unwrap().target().unwrap();
+        let feature_a = commit_on("feature-a", main);
+        let feature_b = commit_on("feature-b", feature_a);
+        commit_on("feature-c", feature_b);
+        commit_on("unrelated", main);
+
+        assert_eq!(
+            find_parent_branch(&repo, "feature-b", "main").as_deref(),
+            Some("feature-a")
+        );
+        assert_eq!(
+            find_parent_branch(&repo, "feature-c", "main").as_deref(),
+            Some("feature-b")
+        );
+        // Branched straight off the base: nothing is closer
+        assert_eq!(find_parent_branch(&repo, "feature-a", "main"), None);
+    }
+
+    #[test]
+    fn test_main_repository_is_never_removable() {
+        let (dir, _repo) = init_repo();
+        let path = dir.path().to_str().unwrap();
+        assert!(ensure_removable(path).is_err());
+        assert!(prepare_removal("main", path, true).is_err());
+        assert!(dir.path().exists());
+    }
+
+    #[test]
+    fn test_collect_worktrees_flags_main_and_current() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().join("wt");
+        add_worktree(&repo, "topic", None, false, &path).unwrap();
+
+        let current = Repository::open(&path).unwrap();
+        let entries = collect_worktrees_from(&current).unwrap();
+
+        let main = entries.iter().find(|e| e.branch == "main").unwrap();
+        assert!(main.is_main && !main.is_current && !main.is_removable());
+        let topic = entries.iter().find(|e| e.branch == "topic").unwrap();
+        assert!(!topic.is_main && topic.is_current && !topic.is_removable());
+        assert_eq!(main.created, None);
+        let created = topic.created.unwrap();
+        assert!((Local::now().timestamp() - created).abs() < 60);
+    }
+
+    #[test]
+    fn test_check_removal_flags_untracked_files() {
+        let (dir, repo) = init_repo();
+        let path = dir.path().join("wt");
+        add_worktree(&repo, "topic", None, false, &path).unwrap();
+        fs::write(path.join("notes.txt"), "wip").unwrap();
+        let path = path.to_str().unwrap();
+
+        let risks = check_removal("topic", path, "origin/main").unwrap();
+        assert_eq!(risks, vec![RemovalRisk::UntrackedFiles(1)]);
+
+        assert!(prepare_removal("topic", path, false).is_err());
+        assert!(Path::new(path).exists());
+        prepare_removal("topic", path, true)
+            .unwrap()
+            .execute()
+            .unwrap();
+        assert!(!Path::new(path).exists());
+    }
+
+    #[test]
+    fn test_renamed_admin_dir_is_listed_and_removed() {
+        let (dir, repo) = init_repo();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let branch = repo.branch("topic", &head, false).unwrap();
+        let reference = branch.into_reference();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(&reference));
+        let path = dir.path().join("checkout");
+        repo.worktree("name1", &path, Some(&opts)).unwrap();
+
+        let entries = collect_worktrees_from(&repo).unwrap();
+        let topic = entries.iter().find(|e| e.branch == "topic").unwrap();
+        assert!(same_path(Path::new(&topic.path), &path));
+
+        prepare_removal("topic", path.to_str().unwrap(), false)
+            .unwrap()
+            .execute()
+            .unwrap();
+        assert!(!path.exists());
+        assert!(!repo.path().join("worktrees").join("name1").exists());
+        assert!(repo.find_branch("topic", BranchType::Local).is_err());
+    }
+
+    #[test]
+    fn test_bare_repository_is_listed_as_bare() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let bare = Repository::init_bare(dir.path().join("repo.git")).unwrap();
+        {
+            let sig = git2::Signature::now("test", "test@example.com").unwrap();
+            let tree_id = bare.treebuilder(None).unwrap().write().unwrap();
+            let tree = bare.find_tree(tree_id).unwrap();
+            bare.commit(Some("refs/heads/main"), &sig, &sig, "initial", &tree, &[])
+                .unwrap();
+            bare.set_head("refs/heads/main").unwrap();
+        }
+        let path = dir.path().join("topic");
+        add_worktree(&bare, "topic", None, false, &path).unwrap();
+
+        let entries = collect_worktrees_from(&bare).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].state, WorktreeState::Bare);
+        assert!(entries[0].is_main && !entries[0].is_removable());
+        assert_eq!(entries[1].branch, "topic");
+        assert_eq!(entries[1].state, WorktreeState::Branch);
+
+        // Seen from inside the linked worktree, the same list comes back
+        let linked = Repository::open(&path).unwrap();
+        let from_linked = collect_worktrees_from(&linked).unwrap();
+        assert_eq!(from_linked.len(), 2);
+        assert!(from_linked[1].is_current);
+    }
+
+    #[test]
+    fn test_detached_locked_and_missing_worktrees_are_listed() {
+        let (dir, repo) = init_repo();
+        let head = repo.head().unwrap().target().unwrap();
+
+        let detached = dir.path().join("detached");
+        add_worktree(&repo, "to-detach", None, false, &detached).unwrap();
+        Repository::open(&detached)
+            .unwrap()
+            .set_head_detached(head)
+            .unwrap();
+
+        let locked = dir.path().join("locked");
+        add_worktree(&repo, "to-lock", None, false, &locked).unwrap();
+        repo.find_worktree("locked")
+            .unwrap()
+            .lock(Some("on usb drive"))
+            .unwrap();
+
+        let missing = dir.path().join("missing");
+        add_worktree(&repo, "gone", None, false, &missing).unwrap();
+        fs::remove_dir_all(&missing).unwrap();
+
+        let entries = collect_worktrees_from(&repo).unwrap();
+        let state_of = |path: &Path| {
+            entries
+                .iter()
+                .find(|e| Path::new(&e.path) == path)
+                .map(|e| (e.branch.clone(), e.state.clone()))
+                .unwrap()
+        };
+
+        let short = short_oid(head);
+        assert_eq!(
+            state_of(&detached),
+            (format!("detached@{short}"), WorktreeState::Detached(short))
+        );
+        assert_eq!(
+            state_of(&locked),
+            (
+                "to-lock".to_string(),
+                WorktreeState::Locked(Some("on usb drive".to_string()))
+            )
+        );
+        assert_eq!(
+            state_of(&missing),
+            ("gone".to_string(), WorktreeState::Missing)
+        );
+    }
+
+    #[test]
+    fn test_quick_collection_leaves_checkouts_to_checkout_state() {
+        let (dir, repo) = init_repo();
+        let head = repo.head().unwrap().target().unwrap();
+
+        let detached = dir.path().join("detached");
+        add_worktree(&repo, "to-detach", None, false, &detached).unwrap();
+        let detached_repo = Repository::open(&detached).unwrap();
+        detached_repo.set_head_detached(head).unwrap();
+        fs::write(detached_repo.path().join("MERGE_HEAD"), head.to_string()).unwrap();
+
+        let missing = dir.path().join("missing");
+        add_worktree(&repo, "gone", None, false, &missing).unwrap();
+        fs::remove_dir_all(&missing).unwrap();
+
+        let emptied = dir.path().join("emptied");
+        add_worktree(&repo, "emptied", None, false, &emptied).unwrap();
+        fs::remove_file(emptied.join(".git")).unwrap();
+
+        let quick = collect_quick_from(&detached_repo).unwrap();
+        let full = collect_worktrees_from(&detached_repo).unwrap();
+        assert_eq!(quick.len(), full.len());
+        for (quick, full) in quick.iter().zip(&full) {
+            assert_eq!(quick.branch, full.branch);
+            assert!(same_path(Path::new(&quick.path), Path::new(&full.path)));
+            assert_eq!(quick.is_main, full.is_main);
+            assert_eq!(quick.is_current, full.is_current);
+            assert_eq!(quick.operation, full.operation);
+            assert_eq!(checkout_state(quick), full.state);
+        }
+
+        let entry_at = |path: &Path| {
+            quick
+                .iter()
+                .find(|e| same_path(Path::new(&e.path), path))
+                .unwrap()
+        };
+        assert!(entry_at(&detached).is_current);
+        assert_eq!(entry_at(&detached).operation, Some(Operation::Merge));
+        assert_eq!(entry_at(&missing).state, WorktreeState::Branch);
+        assert_eq!(checkout_state(entry_at(&missing)), WorktreeState::Missing);
+        assert_eq!(checkout_state(entry_at(&emptied)), WorktreeState::Prunable);
+    }
+}